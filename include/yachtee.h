@@ -0,0 +1,129 @@
+/*
+ * C interface to the yachtee rule engine.
+ *
+ * Build the library with `cargo rustc --release --lib --crate-type cdylib`
+ * and link against target/release/libyachtee.so (yachtee.dll on Windows,
+ * libyachtee.dylib on macOS). tests/c/smoke.c shows a game played through
+ * it.
+ *
+ * A game is created with a seed and the number of players, who take turns
+ * in order. Each turn starts with the dice rolled once; the player may
+ * reroll up to twice, keeping the dice they hold, and then scores the dice
+ * in a category, which passes the turn on. Moves are checked against the
+ * rules, so the scores, totals and bonuses always follow the game.
+ *
+ * Unless stated otherwise, game pointers must come from yachtee_game_new or
+ * yachtee_game_deserialize and not be freed yet, and array arguments must
+ * hold as many elements as their size says. Games are not thread-safe.
+ */
+
+#ifndef YACHTEE_H
+#define YACHTEE_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define YACHTEE_NUM_DICE 5
+#define YACHTEE_NUM_CATEGORIES 13
+#define YACHTEE_MAX_ROLLS 3
+
+/* Results of functions that change a game. */
+#define YACHTEE_OK 0
+#define YACHTEE_INVALID_ARGUMENT 1
+#define YACHTEE_ILLEGAL_MOVE 2
+
+/* Categories, in the order of the score table. */
+enum YachteeCategory {
+    YACHTEE_ONES = 0,
+    YACHTEE_TWOS = 1,
+    YACHTEE_THREES = 2,
+    YACHTEE_FOURS = 3,
+    YACHTEE_FIVES = 4,
+    YACHTEE_SIXES = 5,
+    YACHTEE_THREE_OF_A_KIND = 6,
+    YACHTEE_FOUR_OF_A_KIND = 7,
+    YACHTEE_FULL_HOUSE = 8,
+    YACHTEE_SMALL_STRAIGHT = 9,
+    YACHTEE_LARGE_STRAIGHT = 10,
+    YACHTEE_FIVE_OF_A_KIND = 11,
+    YACHTEE_CHANCE = 12,
+};
+
+typedef struct YachteeGame YachteeGame;
+
+/* Starts a game, or returns NULL if players is 0. The same seed gives the
+ * same dice for the same moves. */
+YachteeGame *yachtee_game_new(uint64_t seed, size_t players);
+
+/* Frees a game. Does nothing for NULL. */
+void yachtee_game_free(YachteeGame *game);
+
+size_t yachtee_game_players(const YachteeGame *game);
+
+/* Player whose turn it is, from 0. */
+size_t yachtee_game_current_player(const YachteeGame *game);
+
+/* How many times the dice have been rolled this turn, from 1 to
+ * YACHTEE_MAX_ROLLS. */
+size_t yachtee_game_roll(const YachteeGame *game);
+
+bool yachtee_game_is_finished(const YachteeGame *game);
+
+/* Whether the current player has a reroll left. */
+bool yachtee_game_can_reroll(const YachteeGame *game);
+
+/* Writes the pips of each die, from 1 to 6. */
+void yachtee_game_dice(const YachteeGame *game, uint8_t dice[YACHTEE_NUM_DICE]);
+
+/* Writes whether the current player may score the dice in each category. A
+ * five of a kind may be scored again for a bonus after a non-zero one. */
+void yachtee_game_legal_categories(const YachteeGame *game,
+                                   bool legal[YACHTEE_NUM_CATEGORIES]);
+
+/* Rolls the dice that aren't held. Returns YACHTEE_ILLEGAL_MOVE if no
+ * rerolls are left or every die is held. */
+int yachtee_game_reroll(YachteeGame *game, const bool hold[YACHTEE_NUM_DICE]);
+
+/* Scores the dice in a category for the current player and passes the turn
+ * on. Returns YACHTEE_INVALID_ARGUMENT for an unknown category and
+ * YACHTEE_ILLEGAL_MOVE if the category can't be scored. */
+int yachtee_game_score(YachteeGame *game, int category);
+
+/* Score of a player in a category, or -1 if it is not filled yet or the
+ * player or category is unknown. */
+int32_t yachtee_game_category_score(const YachteeGame *game, size_t player,
+                                    int category);
+
+/* Totals of a player, or 0 for an unknown player. The upper total includes
+ * the bonus. */
+uint32_t yachtee_game_upper_total(const YachteeGame *game, size_t player);
+uint32_t yachtee_game_upper_section_bonus(const YachteeGame *game,
+                                          size_t player);
+uint32_t yachtee_game_lower_total(const YachteeGame *game, size_t player);
+uint32_t yachtee_game_grand_total(const YachteeGame *game, size_t player);
+
+/* Writes the game as a NUL-terminated JSON string if it fits in len bytes,
+ * and returns its length without the NUL either way, like snprintf. Call it
+ * with a NULL buffer to get the size needed. */
+size_t yachtee_game_serialize(const YachteeGame *game, char *buffer,
+                              size_t len);
+
+/* Restores a game from yachtee_game_serialize, or returns NULL if the
+ * string isn't a saved game or has scores or a count of rolled dice a
+ * game can't reach. */
+YachteeGame *yachtee_game_deserialize(const char *state);
+
+/* Score of dice, given as pips from 1 to 6, in a category, or -1 if a die
+ * or the category is invalid. Needs no game. */
+int32_t yachtee_dice_score(const uint8_t dice[YACHTEE_NUM_DICE], int category);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* YACHTEE_H */
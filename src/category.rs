@@ -1,4 +1,7 @@
+use crate::charset::Charset;
+
 use std::fmt;
+use std::str::FromStr;
 
 pub const CATEGORIES: [Category; 13] = [
     Category::Ones,
@@ -35,7 +38,7 @@ pub const LOWER_SECTION: [Category; 7] = [
     Category::Chance,
 ];
 
-#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum Category {
     Ones,
     Twos,
@@ -52,22 +55,87 @@ pub enum Category {
     Chance,
 }
 
-impl fmt::Display for Category {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+impl Category {
+    pub fn label(self, charset: Charset) -> &'static str {
+        match charset {
+            Charset::Unicode => match self {
+                Category::Ones => "⚀ 1s",
+                Category::Twos => "⚁ 2s",
+                Category::Threes => "⚂ 3s",
+                Category::Fours => "⚃ 4s",
+                Category::Fives => "⚄ 5s",
+                Category::Sixes => "⚅ 6s",
+                _ => self.name(),
+            },
+            Charset::Ascii => self.name(),
+        }
+    }
+
+    /// Identifier used on the command line and in machine-readable output.
+    pub fn id(self) -> &'static str {
         match self {
-            Category::Ones => f.write_str("⚀ 1s"),
-            Category::Twos => f.write_str("⚁ 2s"),
-            Category::Threes => f.write_str("⚂ 3s"),
-            Category::Fours => f.write_str("⚃ 4s"),
-            Category::Fives => f.write_str("⚄ 5s"),
-            Category::Sixes => f.write_str("⚅ 6s"),
-            Category::ThreeOfAKind => f.write_str("3 of a Kind"),
-            Category::FourOfAKind => f.write_str("4 of a Kind"),
-            Category::FullHouse => f.write_str("Full House"),
-            Category::SmallStraight => f.write_str("Small Straight"),
-            Category::LargeStraight => f.write_str("Large Straight"),
-            Category::FiveOfAKind => f.write_str("5 of a Kind"),
-            Category::Chance => f.write_str("Chance"),
+            Category::Ones => "Ones",
+            Category::Twos => "Twos",
+            Category::Threes => "Threes",
+            Category::Fours => "Fours",
+            Category::Fives => "Fives",
+            Category::Sixes => "Sixes",
+            Category::ThreeOfAKind => "ThreeOfAKind",
+            Category::FourOfAKind => "FourOfAKind",
+            Category::FullHouse => "FullHouse",
+            Category::SmallStraight => "SmallStraight",
+            Category::LargeStraight => "LargeStraight",
+            Category::FiveOfAKind => "FiveOfAKind",
+            Category::Chance => "Chance",
         }
     }
+
+    /// Face counted by an upper section category, from 1 to 6.
+    pub fn face(self) -> Option<u32> {
+        match self {
+            Category::Ones => Some(1),
+            Category::Twos => Some(2),
+            Category::Threes => Some(3),
+            Category::Fours => Some(4),
+            Category::Fives => Some(5),
+            Category::Sixes => Some(6),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Category::Ones => "Ones",
+            Category::Twos => "Twos",
+            Category::Threes => "Threes",
+            Category::Fours => "Fours",
+            Category::Fives => "Fives",
+            Category::Sixes => "Sixes",
+            Category::ThreeOfAKind => "3 of a Kind",
+            Category::FourOfAKind => "4 of a Kind",
+            Category::FullHouse => "Full House",
+            Category::SmallStraight => "Small Straight",
+            Category::LargeStraight => "Large Straight",
+            Category::FiveOfAKind => "5 of a Kind",
+            Category::Chance => "Chance",
+        }
+    }
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        f.write_str(self.label(Charset::Unicode))
+    }
+}
+
+impl FromStr for Category {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        CATEGORIES
+            .iter()
+            .find(|category| category.id().eq_ignore_ascii_case(s))
+            .copied()
+            .ok_or_else(|| anyhow::anyhow!("Unknown category: {}", s))
+    }
 }
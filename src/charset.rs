@@ -0,0 +1,38 @@
+use std::env;
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum Charset {
+    Unicode,
+    Ascii,
+}
+
+impl Charset {
+    /// Guesses whether the terminal can display Unicode from the locale
+    /// environment variables, following the usual precedence of
+    /// `LC_ALL` > `LC_CTYPE` > `LANG`.
+    pub fn detect() -> Self {
+        match Self::from_locale(|name| env::var(name).ok()) {
+            Some(charset) => charset,
+            // Windows doesn't set locale variables unless running under
+            // something like MSYS, and its consoles handle Unicode fine.
+            None if cfg!(windows) => Charset::Unicode,
+            None => Charset::Ascii,
+        }
+    }
+
+    /// Like `detect`, with the variables looked up by `var`, such as those
+    /// a remote terminal sends. `None` if none of them are set.
+    pub fn from_locale<F: Fn(&str) -> Option<String>>(var: F) -> Option<Self> {
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .filter_map(|name| var(name))
+            .find(|value| !value.is_empty())?
+            .to_ascii_lowercase();
+
+        if locale.contains("utf-8") || locale.contains("utf8") {
+            Some(Charset::Unicode)
+        } else {
+            Some(Charset::Ascii)
+        }
+    }
+}
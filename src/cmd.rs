@@ -0,0 +1,7 @@
+pub mod odds;
+pub mod score;
+pub mod serve;
+pub mod simulate;
+pub mod ssh_server;
+pub mod tournament;
+pub mod tune;
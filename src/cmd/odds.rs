@@ -0,0 +1,132 @@
+use yachtee::category::{self, Category};
+use yachtee::charset::Charset;
+use yachtee::dice::{Dice, DiceSet};
+use yachtee::odds::{self, Holding};
+use yachtee::MAX_ROLLS;
+
+use anyhow::Result;
+use serde_json::json;
+use structopt::StructOpt;
+
+/// Print exact chances of scoring in categories with the remaining rolls
+#[derive(StructOpt)]
+pub struct OddsCommand {
+    /// Dice held before the next roll, each from 1 to 6
+    #[structopt(long)]
+    keep: Vec<Dice>,
+
+    /// Number of rolls left in the turn
+    #[structopt(long, default_value = "2")]
+    rolls: usize,
+
+    /// Print the distribution of scores for this category only
+    #[structopt(long)]
+    target: Option<Category>,
+
+    /// Hold dice in the best way for each category after the next roll,
+    /// instead of holding only the kept dice
+    #[structopt(long)]
+    optimal: bool,
+
+    /// Print JSON instead of a table
+    #[structopt(long)]
+    json: bool,
+}
+
+impl OddsCommand {
+    pub fn run(self) -> Result<()> {
+        if self.keep.len() > DiceSet::NUM_DICE {
+            anyhow::bail!("At most {} dice can be kept", DiceSet::NUM_DICE);
+        }
+        if self.rolls > MAX_ROLLS {
+            anyhow::bail!("A turn has at most {} rolls", MAX_ROLLS);
+        }
+        if self.rolls == 0 && self.keep.len() < DiceSet::NUM_DICE {
+            anyhow::bail!("All dice must be kept when no rolls are left");
+        }
+
+        let holding = if self.optimal {
+            Holding::Optimal
+        } else {
+            Holding::Fixed
+        };
+
+        match self.target {
+            Some(category) => {
+                let distribution =
+                    odds::score_distribution(category, &self.keep, self.rolls, holding);
+                self.print_distribution(category, &distribution);
+            }
+            None => {
+                let chances: Vec<_> = category::CATEGORIES
+                    .iter()
+                    .map(|category| {
+                        let distribution =
+                            odds::score_distribution(*category, &self.keep, self.rolls, holding);
+                        (*category, odds::scoring_chance(&distribution))
+                    })
+                    .collect();
+                self.print_chances(&chances);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn print_chances(&self, chances: &[(Category, f64)]) {
+        if self.json {
+            let chances: Vec<_> = chances
+                .iter()
+                .map(|(category, chance)| {
+                    json!({
+                        "category": category.id(),
+                        "chance": chance,
+                    })
+                })
+                .collect();
+            println!("{}", json!({ "chances": chances }));
+        } else {
+            for (category, chance) in chances {
+                println!(
+                    "{:16}{:>6.1}%",
+                    category.label(Charset::Ascii),
+                    chance * 100.0
+                );
+            }
+        }
+    }
+
+    fn print_distribution(&self, category: Category, distribution: &[f64]) {
+        let scores = distribution.iter().enumerate().filter(|(_, p)| **p > 0.0);
+
+        if self.json {
+            let scores: Vec<_> = scores
+                .map(|(score, p)| {
+                    json!({
+                        "score": score,
+                        "probability": p,
+                    })
+                })
+                .collect();
+            let output = json!({
+                "category": category.id(),
+                "chance": odds::scoring_chance(distribution),
+                "mean": odds::mean(distribution),
+                "distribution": scores,
+            });
+            println!("{}", output);
+        } else {
+            println!(
+                "{}: {:.1}% to score, {:.2} on average",
+                category.label(Charset::Ascii),
+                odds::scoring_chance(distribution) * 100.0,
+                odds::mean(distribution)
+            );
+            println!();
+            println!("Score  Probability");
+            for (score, p) in scores {
+                println!("{:>5}  {:>10.4}%", score, p * 100.0);
+            }
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use yachtee::category;
+use yachtee::charset::Charset;
+use yachtee::dice::{Dice, DiceSet};
+
+use anyhow::Result;
+use serde_json::json;
+use structopt::StructOpt;
+
+/// Print the score of every category for a roll
+#[derive(StructOpt)]
+pub struct ScoreCommand {
+    /// Five dice, each from 1 to 6
+    #[structopt(required = true)]
+    dice: Vec<Dice>,
+
+    /// Print JSON instead of a table
+    #[structopt(long)]
+    json: bool,
+}
+
+impl ScoreCommand {
+    pub fn run(self) -> Result<()> {
+        let dice_set = DiceSet::from_slice(&self.dice)?;
+
+        if self.json {
+            let scores: Vec<_> = category::CATEGORIES
+                .iter()
+                .map(|category| {
+                    json!({
+                        "category": category.id(),
+                        "score": dice_set.score(*category),
+                    })
+                })
+                .collect();
+            let dice: Vec<_> = dice_set.0.iter().map(|dice| dice.pips()).collect();
+            let output = json!({
+                "dice": dice,
+                "scores": scores,
+            });
+            println!("{}", output);
+        } else {
+            for category in &category::CATEGORIES {
+                println!(
+                    "{:16}{:>3}",
+                    category.label(Charset::Ascii),
+                    dice_set.score(*category)
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
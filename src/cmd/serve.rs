@@ -0,0 +1,33 @@
+use yachtee::server::ServerBuilder;
+
+use anyhow::Result;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+/// Serve games over an HTTP/JSON API for other front-ends
+#[derive(StructOpt)]
+pub struct ServeCommand {
+    /// Port to listen on
+    #[structopt(long, default_value = "8080")]
+    port: u16,
+
+    /// Address to listen on; use 0.0.0.0 to accept other machines
+    #[structopt(long, default_value = "127.0.0.1")]
+    host: String,
+
+    /// Directory to keep the games in, so they survive a restart
+    #[structopt(long)]
+    data_dir: Option<PathBuf>,
+}
+
+impl ServeCommand {
+    pub fn run(self) -> Result<()> {
+        let server = ServerBuilder::new()
+            .address(format!("{}:{}", self.host, self.port))
+            .data_dir(self.data_dir)
+            .build()?;
+        eprintln!("Listening on http://{}", server.address());
+        server.run();
+        Ok(())
+    }
+}
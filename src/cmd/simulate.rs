@@ -0,0 +1,93 @@
+use yachtee::simulator::{self, Sum};
+use yachtee::strategy::{Engine, Strategy};
+
+use anyhow::Result;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde_json::json;
+use structopt::StructOpt;
+
+/// Play games between bots and report how each of them does
+#[derive(StructOpt)]
+pub struct SimulateCommand {
+    /// Bots taking turns in each game, in the same form as the game's --bot
+    #[structopt(long = "bot", number_of_values = 1, required = true)]
+    bots: Vec<String>,
+
+    /// Number of games to play
+    #[structopt(long, default_value = "1000")]
+    games: usize,
+
+    /// Seed for the dice and the bots' chances, to play the same games again
+    #[structopt(long)]
+    seed: Option<u64>,
+
+    /// Print JSON instead of a table
+    #[structopt(long)]
+    json: bool,
+}
+
+impl SimulateCommand {
+    pub fn run(self) -> Result<()> {
+        if self.games == 0 {
+            anyhow::bail!("At least one game is needed");
+        }
+
+        let mut strategies = self
+            .bots
+            .iter()
+            .map(|bot| Ok(bot.parse::<Engine>()?.strategy()))
+            .collect::<Result<Vec<_>>>()?;
+        let mut rng = match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+
+        let mut scores = vec![Sum::default(); strategies.len()];
+        let mut wins = vec![0.0; strategies.len()];
+        for _ in 0..self.games {
+            let mut players: Vec<&mut dyn Strategy> =
+                strategies.iter_mut().map(|s| s.as_mut() as _).collect();
+            let totals = simulator::play_game(&mut players, &mut rng)?;
+            let best = *totals.iter().max().unwrap();
+            let winners = totals.iter().filter(|total| **total == best).count();
+            for (i, total) in totals.iter().enumerate() {
+                scores[i].add(*total as f64);
+                if *total == best {
+                    // a tie shares the win
+                    wins[i] += 1.0 / winners as f64;
+                }
+            }
+        }
+
+        if self.json {
+            let bots: Vec<_> = self
+                .bots
+                .iter()
+                .zip(scores.iter().zip(wins.iter()))
+                .map(|(bot, (score, wins))| {
+                    json!({
+                        "bot": bot,
+                        "mean": score.mean(),
+                        "margin": 1.96 * score.standard_error(),
+                        "wins": wins,
+                    })
+                })
+                .collect();
+            println!("{}", json!({ "games": self.games, "bots": bots }));
+        } else {
+            println!("Bot                     Mean    +/-    Wins");
+            for (bot, (score, wins)) in self.bots.iter().zip(scores.iter().zip(wins.iter())) {
+                println!(
+                    "{:20}{:>8.1}{:>7.1}{:>7.1}%",
+                    bot,
+                    score.mean(),
+                    1.96 * score.standard_error(),
+                    wins / self.games as f64 * 100.0
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,93 @@
+use yachtee::charset::Charset;
+use yachtee::ssh::{SshServerBuilder, MAX_TABLE_PLAYERS, MIN_TABLE_PLAYERS};
+
+use anyhow::{bail, Result};
+use std::fs;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+/// Serve the game over SSH, so others can play it from their terminals
+#[derive(StructOpt)]
+pub struct SshServerCommand {
+    /// Port to listen on
+    #[structopt(long, default_value = "2222")]
+    port: u16,
+
+    /// Address to listen on; use 0.0.0.0 to accept other machines
+    #[structopt(long, default_value = "127.0.0.1")]
+    host: String,
+
+    /// OpenSSH private key file of the ed25519 host key, generated if it
+    /// doesn't exist
+    #[structopt(long, default_value = "ssh_host_ed25519_key")]
+    host_key: PathBuf,
+
+    /// File holding the password every user has to log in with; without
+    /// it, anyone who can reach the port gets in
+    #[structopt(long)]
+    password_file: Option<PathBuf>,
+
+    /// Number of clients connected at once, past which new connections are
+    /// closed
+    #[structopt(long, default_value = "64")]
+    max_connections: usize,
+
+    /// Number of players, from 2 to 6, a table waits for unless opened with
+    /// another number
+    #[structopt(long, default_value = "2")]
+    table_players: usize,
+
+    /// Draw with ASCII characters only for clients that don't send their
+    /// locale
+    #[structopt(long)]
+    ascii: bool,
+
+    /// Show rolled dice immediately instead of tumbling them
+    #[structopt(long)]
+    no_animation: bool,
+}
+
+impl SshServerCommand {
+    pub fn run(self) -> Result<()> {
+        if !(MIN_TABLE_PLAYERS..=MAX_TABLE_PLAYERS).contains(&self.table_players) {
+            bail!(
+                "Tables are for {} to {} players",
+                MIN_TABLE_PLAYERS,
+                MAX_TABLE_PLAYERS
+            );
+        }
+        let charset = if self.ascii {
+            Charset::Ascii
+        } else {
+            Charset::Unicode
+        };
+        let password = match &self.password_file {
+            Some(path) => {
+                let password = fs::read_to_string(path)
+                    .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+                let password = password.trim_end_matches(&['\r', '\n'][..]);
+                if password.is_empty() {
+                    bail!("{} is empty", path.display());
+                }
+                Some(password.to_string())
+            }
+            None => None,
+        };
+        let server = SshServerBuilder::new()
+            .address(format!("{}:{}", self.host, self.port))
+            .host_key(self.host_key)
+            .password(password)
+            .max_connections(self.max_connections)
+            .table_players(self.table_players)
+            .charset(charset)
+            .animation(!self.no_animation)
+            .build()?;
+        eprintln!(
+            "Listening on ssh://{}, host key {}",
+            server.address(),
+            server.fingerprint()
+        );
+        server.run();
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+use yachtee::external::ExternalBot;
+use yachtee::strategy::{Engine, Strategy};
+use yachtee::tournament::{Factory, TournamentBuilder};
+
+use anyhow::Result;
+use serde_json::json;
+use std::time::Duration;
+use structopt::StructOpt;
+
+/// Play round-robin matches between bots and rate them
+#[derive(StructOpt)]
+pub struct TournamentCommand {
+    /// Comma-separated bots: built-in ones in the same form as the game's
+    /// --bot, or paths to programs speaking the external bot protocol
+    #[structopt(long, use_delimiter = true, required = true)]
+    bots: Vec<String>,
+
+    /// Games played by each pair of bots
+    #[structopt(long, default_value = "1000")]
+    games: usize,
+
+    /// Milliseconds each external bot gets for each move
+    #[structopt(long, default_value = "1000")]
+    time_limit: u64,
+
+    /// Seed for the dice and the bots' chances, to play the same games again
+    #[structopt(long)]
+    seed: Option<u64>,
+
+    /// Print JSON instead of tables
+    #[structopt(long)]
+    json: bool,
+}
+
+impl TournamentCommand {
+    pub fn run(self) -> Result<()> {
+        if self.bots.len() < 2 {
+            anyhow::bail!("A tournament needs at least 2 bots");
+        }
+        if self.games == 0 {
+            anyhow::bail!("At least one game is needed");
+        }
+
+        let mut builder = TournamentBuilder::new()
+            .games(self.games)
+            .seed(self.seed.unwrap_or_else(rand::random));
+        for bot in &self.bots {
+            builder = builder.entrant(bot.as_str(), self.factory(bot)?);
+        }
+        let standings = builder.build().run()?;
+
+        let games_per_bot = (self.games * (self.bots.len() - 1)) as f64;
+        if self.json {
+            let bots: Vec<_> = (0..self.bots.len())
+                .map(|i| {
+                    json!({
+                        "bot": standings.names[i],
+                        "mean": standings.scores[i].mean(),
+                        "margin": 1.96 * standings.scores[i].standard_error(),
+                        "wins": standings.wins[i],
+                        "elo": standings.elo[i],
+                        "failure": standings.failures[i],
+                    })
+                })
+                .collect();
+            let pairings: Vec<_> = standings
+                .pairings
+                .iter()
+                .map(|pairing| {
+                    json!({
+                        "first": standings.names[pairing.first],
+                        "second": standings.names[pairing.second],
+                        "wins": pairing.wins,
+                        "losses": pairing.losses,
+                        "draws": pairing.draws,
+                        "score_difference": pairing.score_difference.mean(),
+                        "margin": 1.96 * pairing.score_difference.standard_error(),
+                        "p_value": pairing.p_value(),
+                    })
+                })
+                .collect();
+            let output = json!({
+                "games": self.games,
+                "bots": bots,
+                "pairings": pairings,
+            });
+            println!("{}", output);
+        } else {
+            let mut ranking: Vec<usize> = (0..self.bots.len()).collect();
+            ranking.sort_by(|a, b| standings.elo[*b].partial_cmp(&standings.elo[*a]).unwrap());
+
+            println!("Bot                     Elo    Wins    Mean    +/-");
+            for i in ranking {
+                println!(
+                    "{:20}{:>7.0}{:>7.1}%{:>8.1}{:>7.1}",
+                    standings.names[i],
+                    standings.elo[i],
+                    standings.wins[i] / games_per_bot * 100.0,
+                    standings.scores[i].mean(),
+                    1.96 * standings.scores[i].standard_error()
+                );
+            }
+
+            println!();
+            println!("Pairing                                 W-L-D      Score diff  p-value");
+            for pairing in &standings.pairings {
+                let names = format!(
+                    "{} vs {}",
+                    standings.names[pairing.first], standings.names[pairing.second]
+                );
+                let record = format!("{}-{}-{}", pairing.wins, pairing.losses, pairing.draws);
+                println!(
+                    "{:36}{:>11}{:>+9.1} +/-{:>5.1}{:>9.4}",
+                    names,
+                    record,
+                    pairing.score_difference.mean(),
+                    1.96 * pairing.score_difference.standard_error(),
+                    pairing.p_value()
+                );
+            }
+
+            if standings.failures.iter().any(Option::is_some) {
+                println!();
+            }
+            for failure in standings.failures.iter().flatten() {
+                println!("{}, so it wasn't asked again", failure);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn factory(&self, bot: &str) -> Result<Factory> {
+        match bot.parse::<Engine>() {
+            Ok(engine) => Ok(Box::new(move || Ok(engine.strategy()))),
+            Err(_) if bot.contains(std::path::is_separator) => {
+                let path = bot.to_string();
+                let time_limit = Duration::from_millis(self.time_limit);
+                Ok(Box::new(move || {
+                    Ok(Box::new(ExternalBot::spawn(&path, time_limit)?) as Box<dyn Strategy>)
+                }))
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use yachtee::heuristic::{HeuristicStrategy, Weights};
+use yachtee::simulator::{self, Sum};
+use yachtee::strategy::Strategy;
+
+use anyhow::Result;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::path::PathBuf;
+use std::thread;
+use structopt::StructOpt;
+
+/// Tune the heuristic bot's weights by self-play and save them to a profile
+#[derive(StructOpt)]
+pub struct TuneCommand {
+    /// Profile to write the tuned weights to, for `--bot heuristic:<profile>`
+    #[structopt(long, default_value = "heuristic.json")]
+    output: PathBuf,
+
+    /// Profile to start from, instead of the default weights
+    #[structopt(long)]
+    start: Option<PathBuf>,
+
+    /// Rounds of mutation and selection
+    #[structopt(long, default_value = "15")]
+    generations: usize,
+
+    /// Candidates tried in each generation, including the best one so far
+    #[structopt(long, default_value = "12")]
+    population: usize,
+
+    /// Games played by each candidate in each generation
+    #[structopt(long, default_value = "200")]
+    games: usize,
+
+    /// Games played to compare the tuned weights with the starting ones
+    #[structopt(long, default_value = "2000")]
+    check_games: usize,
+
+    /// Standard deviation of the first generation's mutations, as a share
+    /// of the typical size of each weight
+    #[structopt(long, default_value = "0.2")]
+    step: f64,
+
+    /// Seed for the dice and the mutations
+    #[structopt(long)]
+    seed: Option<u64>,
+}
+
+impl TuneCommand {
+    pub fn run(self) -> Result<()> {
+        if self.population < 2 || self.games < 2 || self.check_games < 2 {
+            anyhow::bail!("Tuning needs a population and games of at least 2");
+        }
+
+        let start = match &self.start {
+            Some(path) => Weights::load(path)?,
+            None => Weights::default(),
+        };
+        let mut rng = match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+
+        // a simple elitist evolution strategy: the candidates of a generation
+        // play the same games, and the mutations shrink as the weights settle
+        let mut best = start;
+        let mut step = self.step;
+        let scales = Weights::scales();
+        for generation in 1..=self.generations {
+            let mut candidates = vec![best];
+            while candidates.len() < self.population {
+                let weights: Vec<f64> = best
+                    .to_vec()
+                    .iter()
+                    .zip(&scales)
+                    .map(|(weight, scale)| weight + step * scale * standard_normal(&mut rng))
+                    .collect();
+                candidates.push(Weights::from_slice(&weights)?);
+            }
+
+            let scores = play(&candidates, self.games, rng.gen());
+            let (index, sum) = scores
+                .iter()
+                .map(|scores| sum(scores))
+                .enumerate()
+                .max_by(|(_, a), (_, b)| a.mean().partial_cmp(&b.mean()).unwrap())
+                .unwrap();
+            best = candidates[index];
+            step *= STEP_DECAY;
+
+            eprintln!(
+                "Generation {}/{}: best mean {:.1} +/- {:.1}",
+                generation,
+                self.generations,
+                sum.mean(),
+                1.96 * sum.standard_error()
+            );
+        }
+
+        best.save(&self.output)?;
+
+        // fresh games, so the comparison isn't biased by the selection
+        let scores = play(&[start, best], self.check_games, rng.gen());
+        let (before, after) = (sum(&scores[0]), sum(&scores[1]));
+        let mut improvement = Sum::default();
+        for (before, after) in scores[0].iter().zip(scores[1].iter()) {
+            improvement.add(*after as f64 - *before as f64);
+        }
+
+        println!("Saved the tuned weights to {}", self.output.display());
+        println!();
+        println!("Weights      Mean    +/-");
+        for (name, sum) in &[("Starting", before), ("Tuned", after)] {
+            println!(
+                "{:10}{:>7.1}{:>7.1}",
+                name,
+                sum.mean(),
+                1.96 * sum.standard_error()
+            );
+        }
+        println!();
+        println!(
+            "Improvement: {:+.1} +/- {:.1} points per game over {} games",
+            improvement.mean(),
+            1.96 * improvement.standard_error(),
+            self.check_games
+        );
+
+        Ok(())
+    }
+}
+
+const STEP_DECAY: f64 = 0.85;
+
+/// Plays `games` solo games with each of `candidates`, with the same dice for
+/// every candidate, and returns the scores of each candidate in game order.
+fn play(candidates: &[Weights], games: usize, seed: u64) -> Vec<Vec<u32>> {
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let results: Vec<Vec<Vec<u32>>> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|thread| {
+                scope.spawn(move || {
+                    let mut strategy = HeuristicStrategy::new(Weights::default());
+                    candidates
+                        .iter()
+                        .map(|weights| {
+                            strategy.set_weights(*weights);
+                            (thread..games)
+                                .step_by(threads)
+                                .map(|game| {
+                                    let mut rngs = [StdRng::seed_from_u64(seed ^ game as u64)];
+                                    let mut players = [&mut strategy as &mut dyn Strategy];
+                                    let totals =
+                                        simulator::play_game_with_dice(&mut players, &mut rngs);
+                                    totals.unwrap()[0]
+                                })
+                                .collect()
+                        })
+                        .collect()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect()
+    });
+
+    // put the games played by each thread back in order
+    (0..candidates.len())
+        .map(|candidate| {
+            (0..games)
+                .map(|game| results[game % threads][candidate][game / threads])
+                .collect()
+        })
+        .collect()
+}
+
+fn sum(scores: &[u32]) -> Sum {
+    let mut sum = Sum::default();
+    for score in scores {
+        sum.add(*score as f64);
+    }
+    sum
+}
+
+fn standard_normal<R: Rng>(rng: &mut R) -> f64 {
+    // Box-Muller transform
+    let u: f64 = 1.0 - rng.gen::<f64>();
+    let v: f64 = rng.gen();
+    (-2.0 * u.ln()).sqrt() * (2.0 * std::f64::consts::PI * v).cos()
+}
@@ -1,14 +1,19 @@
 use crate::category::Category;
+use crate::charset::Charset;
 
 use anyhow::Result;
+#[cfg(not(target_arch = "wasm32"))]
 use crossterm::style::{ContentStyle, StyledContent};
+#[cfg(not(target_arch = "wasm32"))]
 use crossterm::{cursor, style};
 use itertools::Itertools;
 use rand::distributions::{Distribution, Standard};
 use rand::Rng;
+#[cfg(not(target_arch = "wasm32"))]
 use std::io;
+use std::str::FromStr;
 
-#[derive(Default, Copy, Clone, PartialEq)]
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
 pub struct Dice(u8);
 
 impl From<u8> for Dice {
@@ -17,6 +22,17 @@ impl From<u8> for Dice {
     }
 }
 
+impl FromStr for Dice {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.parse::<u8>() {
+            Ok(x) if 1 <= x && x <= Dice::NUM_FACES as u8 => Ok(Dice(x - 1)),
+            _ => Err(anyhow::anyhow!("Dice must be a number from 1 to 6: {}", s)),
+        }
+    }
+}
+
 impl Distribution<Dice> for Standard {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Dice {
         Dice(rng.gen_range(0, Dice::NUM_FACES as u8))
@@ -28,21 +44,44 @@ impl Dice {
     pub const WIDTH: u16 = 9;
     pub const HEIGHT: u16 = 5;
 
-    pub fn draw<W: io::Write>(&self, w: &mut W, x: u16, y: u16) -> Result<()> {
-        self.draw_styled(w, x, y, ContentStyle::new())
+    /// Number of pips, from 1 to 6.
+    pub fn pips(self) -> u8 {
+        self.0 + 1
+    }
+
+    /// Face with one more pip, going around from 6 back to 1.
+    pub fn succ(self) -> Dice {
+        Dice((self.0 + 1) % Dice::NUM_FACES as u8)
+    }
+
+    /// Face with one less pip, going around from 1 back to 6.
+    pub fn pred(self) -> Dice {
+        Dice((self.0 + Dice::NUM_FACES as u8 - 1) % Dice::NUM_FACES as u8)
+    }
+
+    /// Picture of the face, `HEIGHT` lines of `WIDTH` characters.
+    pub fn face(self, charset: Charset) -> &'static str {
+        match charset {
+            Charset::Unicode => DICE_FACES[self.0 as usize],
+            Charset::Ascii => ASCII_DICE_FACES[self.0 as usize],
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn draw<W: io::Write>(&self, w: &mut W, x: u16, y: u16, charset: Charset) -> Result<()> {
+        self.draw_styled(w, x, y, charset, ContentStyle::new())
     }
 
+    #[cfg(not(target_arch = "wasm32"))]
     pub fn draw_styled<W: io::Write>(
         &self,
         w: &mut W,
         x: u16,
         y: u16,
+        charset: Charset,
         style: ContentStyle,
     ) -> Result<()> {
-        for (line, y) in DICE_FACES[self.0 as usize]
-            .split('\n')
-            .zip(y..Dice::HEIGHT + y)
-        {
+        for (line, y) in self.face(charset).split('\n').zip(y..Dice::HEIGHT + y) {
             crossterm::queue!(
                 w,
                 cursor::MoveTo(x, y),
@@ -54,6 +93,7 @@ impl Dice {
     }
 }
 
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
 pub struct DiceSet(pub [Dice; Self::NUM_DICE]);
 
 impl Distribution<DiceSet> for Standard {
@@ -62,9 +102,25 @@ impl Distribution<DiceSet> for Standard {
     }
 }
 
+/// Dice showing `pips`, each from 1 to 6, for tests.
+#[cfg(test)]
+pub(crate) fn dice_set(pips: [u8; DiceSet::NUM_DICE]) -> DiceSet {
+    DiceSet::from_slice(&pips.map(|pips| Dice::from(pips - 1))).unwrap()
+}
+
 impl DiceSet {
     pub const NUM_DICE: usize = 5;
 
+    pub fn from_slice(dice: &[Dice]) -> Result<Self> {
+        if dice.len() != Self::NUM_DICE {
+            anyhow::bail!("Expected {} dice, but got {}", Self::NUM_DICE, dice.len());
+        }
+
+        let mut dice_set = Self::default();
+        dice_set.0.copy_from_slice(dice);
+        Ok(dice_set)
+    }
+
     pub fn score(&self, category: Category) -> u32 {
         match category {
             Category::Ones => self.count(0) as u32,
@@ -81,7 +137,7 @@ impl DiceSet {
                     for x in &self.0 {
                         counts[x.0 as usize] += 1;
                     }
-                    counts.iter().any(|x| *x == 2) && counts.iter().any(|x| *x == 3)
+                    counts.contains(&2) && counts.contains(&3)
                 } =>
             {
                 25
@@ -155,3 +211,36 @@ const DICE_FACES: [&str; Dice::NUM_FACES] = [
      │ ●   ● │\n\
      ╰───────╯",
 ];
+
+const ASCII_DICE_FACES: [&str; Dice::NUM_FACES] = [
+    "+-------+\n\
+     |       |\n\
+     |   o   |\n\
+     |       |\n\
+     +-------+",
+    "+-------+\n\
+     | o     |\n\
+     |       |\n\
+     |     o |\n\
+     +-------+",
+    "+-------+\n\
+     | o     |\n\
+     |   o   |\n\
+     |     o |\n\
+     +-------+",
+    "+-------+\n\
+     | o   o |\n\
+     |       |\n\
+     | o   o |\n\
+     +-------+",
+    "+-------+\n\
+     | o   o |\n\
+     |   o   |\n\
+     | o   o |\n\
+     +-------+",
+    "+-------+\n\
+     | o   o |\n\
+     | o   o |\n\
+     | o   o |\n\
+     +-------+",
+];
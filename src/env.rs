@@ -0,0 +1,351 @@
+//! Gym-style environment for training agents, one player per environment.
+//!
+//! Actions are numbered from 0 to `NUM_ACTIONS - 1`. The first `NUM_HOLDS`
+//! keep the dice whose bits are set in the action (bit `i` for die `i`) and
+//! roll the others again, and the rest score the dice in the category at
+//! `action - NUM_HOLDS` of `category::CATEGORIES`. Rerolling no dice is not
+//! a legal action, so a turn ends only by scoring.
+//!
+//! Observations are `OBSERVATION_LEN` numbers: each die's face one-hot
+//! encoded, the roll number one-hot encoded, whether each category is
+//! filled, each category's score, the upper section subtotal and the grand
+//! total. Scores are not scaled. Rewards are the points each action adds to
+//! the grand total, bonuses included.
+
+use crate::category;
+use crate::dice::{Dice, DiceSet};
+use crate::odds;
+use crate::scoreboard::Scoreboard;
+use crate::state::GameState;
+use crate::strategy::Decision;
+use crate::MAX_ROLLS;
+
+use anyhow::Result;
+use std::thread;
+
+pub const NUM_HOLDS: usize = 1 << DiceSet::NUM_DICE;
+pub const NUM_ACTIONS: usize = NUM_HOLDS + category::CATEGORIES.len();
+pub const OBSERVATION_LEN: usize =
+    DiceSet::NUM_DICE * Dice::NUM_FACES + MAX_ROLLS + 2 * category::CATEGORIES.len() + 2;
+
+/// Turns an action into what it does.
+pub fn decode_action(action: usize) -> Option<Decision> {
+    if action < NUM_HOLDS {
+        let mut rerolled = [false; DiceSet::NUM_DICE];
+        for (i, rerolled) in rerolled.iter_mut().enumerate() {
+            *rerolled = action & (1 << i) == 0;
+        }
+        Some(Decision::Reroll(rerolled))
+    } else {
+        category::CATEGORIES
+            .get(action - NUM_HOLDS)
+            .map(|category| Decision::Score(*category))
+    }
+}
+
+pub fn encode_action(decision: Decision) -> usize {
+    match decision {
+        Decision::Reroll(rerolled) => rerolled
+            .iter()
+            .enumerate()
+            .filter(|(_, rerolled)| !**rerolled)
+            .map(|(i, _)| 1 << i)
+            .sum(),
+        Decision::Score(category) => NUM_HOLDS + odds::category_index(category),
+    }
+}
+
+pub struct Env {
+    state: GameState,
+}
+
+impl Env {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: GameState::new(1, seed).unwrap(),
+        }
+    }
+
+    /// Starts a new game and returns the first observation.
+    pub fn reset(&mut self, seed: u64) -> Vec<f32> {
+        self.state = GameState::new(1, seed).unwrap();
+        self.observation()
+    }
+
+    /// Plays `action` and returns the next observation, the reward and
+    /// whether the game is over.
+    pub fn step(&mut self, action: usize) -> Result<(Vec<f32>, f32, bool)> {
+        if !self.action_is_legal(action) {
+            anyhow::bail!("Illegal action: {}", action);
+        }
+
+        let mut reward = 0.0;
+        match decode_action(action).unwrap() {
+            Decision::Reroll(rerolled) => {
+                let mut held = [false; DiceSet::NUM_DICE];
+                for (held, rerolled) in held.iter_mut().zip(rerolled.iter()) {
+                    *held = !rerolled;
+                }
+                self.state.set_held(held);
+                self.state.reroll()?;
+            }
+            Decision::Score(category) => {
+                reward = self.state.choose_category(category)? as f32;
+            }
+        }
+
+        Ok((self.observation(), reward, self.is_done()))
+    }
+
+    pub fn action_is_legal(&self, action: usize) -> bool {
+        match decode_action(action) {
+            Some(Decision::Reroll(rerolled)) => {
+                self.state.can_reroll() && rerolled != [false; DiceSet::NUM_DICE]
+            }
+            Some(Decision::Score(category)) => self.state.category_is_available(category),
+            None => false,
+        }
+    }
+
+    pub fn legal_action_mask(&self) -> Vec<bool> {
+        (0..NUM_ACTIONS)
+            .map(|action| self.action_is_legal(action))
+            .collect()
+    }
+
+    pub fn observation(&self) -> Vec<f32> {
+        let scoreboard = self.scoreboard();
+        let mut observation = Vec::with_capacity(OBSERVATION_LEN);
+        for dice in &self.dice_set().0 {
+            for face in 1..=Dice::NUM_FACES as u8 {
+                observation.push((dice.pips() == face) as u8 as f32);
+            }
+        }
+        for roll in 1..=MAX_ROLLS {
+            observation.push((self.roll() == roll) as u8 as f32);
+        }
+        for category in &category::CATEGORIES {
+            let filled = scoreboard.category_score(*category).is_some();
+            observation.push(filled as u8 as f32);
+        }
+        for category in &category::CATEGORIES {
+            let score = scoreboard.category_score(*category).unwrap_or(0);
+            observation.push(score as f32);
+        }
+        observation.push(scoreboard.basic_total() as f32);
+        observation.push(scoreboard.grand_total() as f32);
+        observation
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.state.is_finished()
+    }
+
+    pub fn dice_set(&self) -> &DiceSet {
+        self.state.dice_set()
+    }
+
+    /// How many times the dice have been rolled this turn, starting from 1.
+    pub fn roll(&self) -> usize {
+        self.state.roll()
+    }
+
+    pub fn scoreboard(&self) -> &Scoreboard {
+        self.state.scoreboard()
+    }
+}
+
+/// Many environments stepped together, with observations and masks laid out
+/// one environment after another.
+pub struct BatchEnv {
+    envs: Vec<Env>,
+}
+
+impl BatchEnv {
+    pub fn new(seeds: &[u64]) -> Self {
+        Self {
+            envs: seeds.iter().map(|seed| Env::new(*seed)).collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.envs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.envs.is_empty()
+    }
+
+    pub fn reset(&mut self, seeds: &[u64]) -> Result<Vec<f32>> {
+        self.check_len(seeds.len())?;
+        Ok(self
+            .envs
+            .iter_mut()
+            .zip(seeds)
+            .flat_map(|(env, seed)| env.reset(*seed))
+            .collect())
+    }
+
+    /// Plays one action in each environment. Environments that are done are
+    /// left alone and get a reward of 0, whatever their action. If any action
+    /// is illegal, no environment is stepped.
+    pub fn step(&mut self, actions: &[usize]) -> Result<(Vec<f32>, Vec<f32>, Vec<bool>)> {
+        self.check_len(actions.len())?;
+        for (i, (env, action)) in self.envs.iter().zip(actions).enumerate() {
+            if !env.is_done() && !env.action_is_legal(*action) {
+                anyhow::bail!("Illegal action in environment {}: {}", i, action);
+            }
+        }
+
+        let step = |envs: &mut [Env], actions: &[usize]| -> Result<Vec<(Vec<f32>, f32, bool)>> {
+            envs.iter_mut()
+                .zip(actions)
+                .map(|(env, action)| {
+                    if env.is_done() {
+                        Ok((env.observation(), 0.0, true))
+                    } else {
+                        env.step(*action)
+                    }
+                })
+                .collect()
+        };
+
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        let results = if self.envs.len() < PARALLEL_BATCH_LEN || threads == 1 {
+            step(&mut self.envs, actions)?
+        } else {
+            let chunk_len = self.envs.len().div_ceil(threads);
+            let chunks = thread::scope(|scope| {
+                let handles: Vec<_> = self
+                    .envs
+                    .chunks_mut(chunk_len)
+                    .zip(actions.chunks(chunk_len))
+                    .map(|(envs, actions)| scope.spawn(move || step(envs, actions)))
+                    .collect();
+                handles
+                    .into_iter()
+                    .map(|handle| handle.join().unwrap())
+                    .collect::<Result<Vec<_>>>()
+            })?;
+            chunks.into_iter().flatten().collect()
+        };
+
+        let mut observations = Vec::with_capacity(results.len() * OBSERVATION_LEN);
+        let mut rewards = Vec::with_capacity(results.len());
+        let mut dones = Vec::with_capacity(results.len());
+        for (observation, reward, done) in results {
+            observations.extend(observation);
+            rewards.push(reward);
+            dones.push(done);
+        }
+        Ok((observations, rewards, dones))
+    }
+
+    pub fn legal_action_masks(&self) -> Vec<bool> {
+        self.envs
+            .iter()
+            .flat_map(|env| env.legal_action_mask())
+            .collect()
+    }
+
+    pub fn envs(&self) -> &[Env] {
+        &self.envs
+    }
+
+    fn check_len(&self, len: usize) -> Result<()> {
+        if len != self.envs.len() {
+            anyhow::bail!("Expected {} values, but got {}", self.envs.len(), len);
+        }
+        Ok(())
+    }
+}
+
+/// Batches at least this large are stepped on several threads.
+const PARALLEL_BATCH_LEN: usize = 1024;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The first category the dice can be scored in.
+    fn first_score(env: &Env) -> usize {
+        (NUM_HOLDS..NUM_ACTIONS)
+            .find(|action| env.action_is_legal(*action))
+            .unwrap()
+    }
+
+    #[test]
+    fn legal_action_mask() {
+        let mut env = Env::new(1);
+        let mask = env.legal_action_mask();
+        assert_eq!(mask.len(), NUM_ACTIONS);
+        // every hold but keeping all the dice, and every category
+        assert!(!mask[NUM_HOLDS - 1]);
+        assert_eq!(mask.iter().filter(|legal| **legal).count(), NUM_ACTIONS - 1);
+
+        env.step(0).unwrap();
+        env.step(0).unwrap();
+        assert_eq!(env.roll(), MAX_ROLLS);
+        let mask = env.legal_action_mask();
+        assert!(mask[..NUM_HOLDS].iter().all(|legal| !legal));
+        assert!(env.step(0).is_err());
+
+        let action = first_score(&env);
+        env.step(action).unwrap();
+        let category = category::CATEGORIES[action - NUM_HOLDS];
+        let open = env
+            .scoreboard()
+            .category_is_available(category, env.dice_set());
+        assert_eq!(env.legal_action_mask()[action], open);
+        assert!(!env.action_is_legal(NUM_ACTIONS));
+    }
+
+    #[test]
+    fn rewards_add_up_to_the_final_score() {
+        let mut env = Env::new(2);
+        let mut total = 0.0;
+        loop {
+            let (observation, reward, done) = env.step(first_score(&env)).unwrap();
+            assert_eq!(observation.len(), OBSERVATION_LEN);
+            total += reward;
+            if done {
+                break;
+            }
+            assert!(!env.is_done());
+        }
+        assert_eq!(total, env.scoreboard().grand_total() as f32);
+        assert!(env.legal_action_mask().iter().all(|legal| !legal));
+        assert!(env.step(NUM_HOLDS).is_err());
+    }
+
+    #[test]
+    fn batch_step_checks_every_action_first() {
+        let mut batch = BatchEnv::new(&[3, 4]);
+        let before = batch.reset(&[3, 4]).unwrap();
+        // the first action is legal, the second keeps every die
+        assert!(batch.step(&[0, NUM_HOLDS - 1]).is_err());
+        assert_eq!(batch.envs()[0].roll(), 1);
+        let after: Vec<f32> = batch.envs().iter().flat_map(Env::observation).collect();
+        assert_eq!(before, after);
+        assert!(batch.step(&[0]).is_err());
+    }
+
+    #[test]
+    fn batch_leaves_finished_games_alone() {
+        let mut batch = BatchEnv::new(&[5, 6]);
+        while !batch.envs().iter().all(Env::is_done) {
+            let actions: Vec<_> = batch
+                .envs()
+                .iter()
+                .map(|env| if env.is_done() { 0 } else { first_score(env) })
+                .collect();
+            batch.step(&actions).unwrap();
+        }
+        let first = batch.envs()[0].observation();
+        // any action goes for a finished game
+        let (observations, rewards, dones) = batch.step(&[NUM_ACTIONS, NUM_ACTIONS]).unwrap();
+        assert_eq!(observations[..OBSERVATION_LEN], first[..]);
+        assert_eq!(rewards, [0.0, 0.0]);
+        assert_eq!(dones, [true, true]);
+    }
+}
@@ -0,0 +1,197 @@
+//! Bots running as separate programs, speaking JSON over stdin and stdout.
+//!
+//! For every move the program is sent one line with a request and must answer
+//! with one line before the time limit. Requests look like
+//!
+//! ```text
+//! {"type":"hold","roll":1,"dice":[2,2,5,6,1],"scoreboard":{"Ones":3},"opponents":[{}]}
+//! ```
+//!
+//! where `type` is `hold` or `category`, and scoreboards map the ids of the
+//! filled categories to their scores. The answers are
+//!
+//! ```text
+//! {"hold":[true,true,false,false,false]}
+//! {"category":"FullHouse"}
+//! ```
+//!
+//! A program that is too slow, exits or answers with an invalid move is
+//! stopped, and the rest of its moves keep every die and take the first
+//! available category. Why it was stopped is left to `Strategy::failure`.
+
+use crate::category::{self, Category};
+use crate::dice::DiceSet;
+use crate::scoreboard::Scoreboard;
+use crate::strategy::{Decision, Strategy, Turn};
+use crate::MAX_ROLLS;
+
+use anyhow::Result;
+use crossbeam_channel::Receiver;
+use rand::RngCore;
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::time::Duration;
+
+pub struct ExternalBot {
+    name: String,
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+    time_limit: Duration,
+    failure: Option<String>,
+}
+
+impl ExternalBot {
+    /// Starts the program at `path`.
+    pub fn spawn(path: &str, time_limit: Duration) -> Result<Self> {
+        let mut child = Command::new(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| anyhow::anyhow!("Failed to start {}: {}", path, e))?;
+
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let (tx, lines) = crossbeam_channel::unbounded();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Self {
+            name: path.to_string(),
+            child,
+            stdin,
+            lines,
+            time_limit,
+            failure: None,
+        })
+    }
+
+    fn request(&mut self, kind: &str, turn: &Turn) -> Option<Value> {
+        if self.failure.is_some() {
+            return None;
+        }
+
+        let request = json!({
+            "type": kind,
+            "roll": turn.roll,
+            "dice": turn.dice_set.0.iter().map(|dice| dice.pips()).collect::<Vec<_>>(),
+            "scoreboard": scoreboard_json(turn.scoreboard),
+            "opponents": turn.opponents.iter().map(|s| scoreboard_json(s)).collect::<Vec<_>>(),
+        });
+        if writeln!(self.stdin, "{}", request).is_err() {
+            self.fail("exited");
+            return None;
+        }
+
+        match self.lines.recv_timeout(self.time_limit) {
+            Ok(line) => match serde_json::from_str(&line) {
+                Ok(answer) => Some(answer),
+                Err(_) => {
+                    self.fail(&format!("sent an invalid answer: {}", line));
+                    None
+                }
+            },
+            Err(_) => {
+                self.fail("didn't answer in time");
+                None
+            }
+        }
+    }
+
+    fn fail(&mut self, reason: &str) {
+        self.failure = Some(format!("{} {}", self.name, reason));
+        let _ = self.child.kill();
+    }
+
+    /// Asks which dice to keep, keeping them all if the bot fails.
+    fn holds(&mut self, turn: &Turn) -> [bool; DiceSet::NUM_DICE] {
+        let answer = match self.request("hold", turn) {
+            Some(answer) => answer,
+            None => return [true; DiceSet::NUM_DICE],
+        };
+
+        let held: Option<Vec<bool>> = answer["hold"]
+            .as_array()
+            .map(|held| held.iter().filter_map(Value::as_bool).collect());
+        match held {
+            Some(held) if held.len() == DiceSet::NUM_DICE => {
+                let mut holds = [false; DiceSet::NUM_DICE];
+                holds.copy_from_slice(&held);
+                holds
+            }
+            _ => {
+                self.fail(&format!("sent an invalid hold: {}", answer));
+                [true; DiceSet::NUM_DICE]
+            }
+        }
+    }
+
+    /// Asks where to score, falling back to the first available category
+    /// if the bot fails.
+    fn category(&mut self, turn: &Turn) -> Category {
+        let fallback = turn.first_available();
+        let answer = match self.request("category", turn) {
+            Some(answer) => answer,
+            None => return fallback,
+        };
+
+        let category = answer["category"]
+            .as_str()
+            .and_then(|category| category.parse::<Category>().ok());
+        match category {
+            Some(category)
+                if turn
+                    .scoreboard
+                    .category_is_available(category, turn.dice_set) =>
+            {
+                category
+            }
+            _ => {
+                self.fail(&format!("sent an invalid category: {}", answer));
+                fallback
+            }
+        }
+    }
+}
+
+impl Strategy for ExternalBot {
+    /// Asks for the dice to hold while they can be rolled again, and where
+    /// to score them once all of them are held.
+    fn decide(&mut self, turn: &Turn, _rng: &mut dyn RngCore) -> Decision {
+        if turn.roll < MAX_ROLLS {
+            let held = self.holds(turn);
+            if held != [true; DiceSet::NUM_DICE] {
+                return Decision::Reroll(held.map(|held| !held));
+            }
+        }
+        Decision::Score(self.category(turn))
+    }
+
+    fn failure(&self) -> Option<String> {
+        self.failure.clone()
+    }
+}
+
+impl Drop for ExternalBot {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn scoreboard_json(scoreboard: &Scoreboard) -> Value {
+    let scores: serde_json::Map<_, _> = category::CATEGORIES
+        .iter()
+        .filter_map(|category| {
+            let score = scoreboard.category_score(*category)?;
+            Some((category.id().to_string(), json!(score)))
+        })
+        .collect();
+    Value::Object(scores)
+}
@@ -0,0 +1,195 @@
+//! C ABI of the rule engine, declared in `include/yachtee.h`.
+//!
+//! Every function taking a `YachteeGame` pointer expects one returned by
+//! `yachtee_game_new` or `yachtee_game_deserialize` and not yet freed, and
+//! array arguments must point to as many elements as the header says.
+
+use crate::category::{self, Category};
+use crate::dice::{Dice, DiceSet};
+use crate::scoreboard::Scoreboard;
+use crate::state::GameState;
+
+use std::convert::TryFrom;
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int};
+use std::ptr;
+
+pub struct YachteeGame(GameState);
+
+const YACHTEE_OK: c_int = 0;
+const YACHTEE_INVALID_ARGUMENT: c_int = 1;
+const YACHTEE_ILLEGAL_MOVE: c_int = 2;
+
+fn category_at(category: c_int) -> Option<Category> {
+    category::CATEGORIES
+        .get(usize::try_from(category).ok()?)
+        .copied()
+}
+
+fn player_scoreboard(game: &YachteeGame, player: usize) -> Option<&Scoreboard> {
+    game.0.scoreboards().get(player)
+}
+
+#[no_mangle]
+pub extern "C" fn yachtee_game_new(seed: u64, players: usize) -> *mut YachteeGame {
+    match GameState::new(players, seed) {
+        Ok(state) => Box::into_raw(Box::new(YachteeGame(state))),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn yachtee_game_free(game: *mut YachteeGame) {
+    if !game.is_null() {
+        drop(Box::from_raw(game));
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn yachtee_game_players(game: *const YachteeGame) -> usize {
+    (*game).0.players()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn yachtee_game_current_player(game: *const YachteeGame) -> usize {
+    (*game).0.current_player()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn yachtee_game_roll(game: *const YachteeGame) -> usize {
+    (*game).0.roll()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn yachtee_game_is_finished(game: *const YachteeGame) -> bool {
+    (*game).0.is_finished()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn yachtee_game_can_reroll(game: *const YachteeGame) -> bool {
+    (*game).0.can_reroll()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn yachtee_game_dice(game: *const YachteeGame, dice: *mut u8) {
+    let dice = std::slice::from_raw_parts_mut(dice, DiceSet::NUM_DICE);
+    for (pips, die) in dice.iter_mut().zip((*game).0.dice_set().0.iter()) {
+        *pips = die.pips();
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn yachtee_game_legal_categories(game: *const YachteeGame, legal: *mut bool) {
+    let legal = std::slice::from_raw_parts_mut(legal, category::CATEGORIES.len());
+    for (legal, category) in legal.iter_mut().zip(category::CATEGORIES.iter()) {
+        *legal = (*game).0.category_is_available(*category);
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn yachtee_game_reroll(game: *mut YachteeGame, hold: *const bool) -> c_int {
+    let mut held = [false; DiceSet::NUM_DICE];
+    held.copy_from_slice(std::slice::from_raw_parts(hold, DiceSet::NUM_DICE));
+
+    let state = &mut (*game).0;
+    if !state.can_reroll() || held.iter().all(|held| *held) {
+        return YACHTEE_ILLEGAL_MOVE;
+    }
+    state.set_held(held);
+    match state.reroll() {
+        Ok(()) => YACHTEE_OK,
+        Err(_) => YACHTEE_ILLEGAL_MOVE,
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn yachtee_game_score(game: *mut YachteeGame, category: c_int) -> c_int {
+    let category = match category_at(category) {
+        Some(category) => category,
+        None => return YACHTEE_INVALID_ARGUMENT,
+    };
+    match (*game).0.choose_category(category) {
+        Ok(_) => YACHTEE_OK,
+        Err(_) => YACHTEE_ILLEGAL_MOVE,
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn yachtee_game_category_score(
+    game: *const YachteeGame,
+    player: usize,
+    category: c_int,
+) -> i32 {
+    let score = player_scoreboard(&*game, player)
+        .zip(category_at(category))
+        .and_then(|(scoreboard, category)| scoreboard.category_score(category));
+    match score {
+        Some(score) => score as i32,
+        None => -1,
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn yachtee_game_upper_total(game: *const YachteeGame, player: usize) -> u32 {
+    player_scoreboard(&*game, player).map_or(0, Scoreboard::upper_total)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn yachtee_game_upper_section_bonus(
+    game: *const YachteeGame,
+    player: usize,
+) -> u32 {
+    player_scoreboard(&*game, player).map_or(0, Scoreboard::upper_section_bonus)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn yachtee_game_lower_total(game: *const YachteeGame, player: usize) -> u32 {
+    player_scoreboard(&*game, player).map_or(0, Scoreboard::lower_total)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn yachtee_game_grand_total(game: *const YachteeGame, player: usize) -> u32 {
+    player_scoreboard(&*game, player).map_or(0, Scoreboard::grand_total)
+}
+
+/// Writes the state as a NUL-terminated JSON string if it fits in `len`
+/// bytes, and returns its length without the NUL either way, like
+/// `snprintf`.
+#[no_mangle]
+pub unsafe extern "C" fn yachtee_game_serialize(
+    game: *const YachteeGame,
+    buffer: *mut c_char,
+    len: usize,
+) -> usize {
+    let json = (*game).0.to_json().to_string();
+    if !buffer.is_null() && json.len() < len {
+        ptr::copy_nonoverlapping(json.as_ptr(), buffer as *mut u8, json.len());
+        *buffer.add(json.len()) = 0;
+    }
+    json.len()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn yachtee_game_deserialize(state: *const c_char) -> *mut YachteeGame {
+    let state = CStr::from_ptr(state)
+        .to_str()
+        .ok()
+        .and_then(|state| serde_json::from_str(state).ok())
+        .and_then(|state| GameState::from_json(&state).ok());
+    match state {
+        Some(state) => Box::into_raw(Box::new(YachteeGame(state))),
+        None => ptr::null_mut(),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn yachtee_dice_score(dice: *const u8, category: c_int) -> i32 {
+    let dice: Option<Vec<Dice>> = std::slice::from_raw_parts(dice, DiceSet::NUM_DICE)
+        .iter()
+        .map(|pips| pips.to_string().parse().ok())
+        .collect();
+    match (dice, category_at(category)) {
+        (Some(dice), Some(category)) => DiceSet::from_slice(&dice).unwrap().score(category) as i32,
+        _ => -1,
+    }
+}
@@ -0,0 +1,1481 @@
+use crate::category;
+use crate::charset::Charset;
+use crate::dice::{Dice, DiceSet};
+use crate::help::HelpScreen;
+use crate::odds::OddsTable;
+use crate::rollout;
+use crate::scoreboard::{self, Scoreboard};
+use crate::solver::{Objective, Solver};
+use crate::strategy::{Decision, Engine, Strategy, Turn};
+use crate::table_printer;
+use crate::MAX_ROLLS;
+
+use anyhow::Result;
+use crossbeam_channel::{select, Receiver, Sender};
+use crossterm::event::{self, Event, KeyCode, KeyModifiers};
+use crossterm::{cursor, style, terminal};
+use rand::Rng;
+use std::io::{self, Write};
+use std::sync::{Arc, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const MAX_MESSAGE_LINES: usize = 4;
+/// Chat messages shown at once, below the message area.
+const CHAT_LINES: usize = 4;
+/// Chat messages kept for scrolling back.
+const CHAT_SCROLLBACK: usize = 100;
+const MAX_CHAT_INPUT_LEN: usize = 60;
+/// Rows of the chat pane: a title, the messages and the input line.
+const CHAT_HEIGHT: u16 = 1 + CHAT_LINES as u16 + 1;
+/// Messages sent with a single key in networked games.
+const EMOTES: [(char, &str); 2] = [('n', "nice roll!"), ('o', "ouch")];
+const DICE_COLUMN_HEIGHT: u16 = Dice::HEIGHT * DiceSet::NUM_DICE as u16;
+/// Rows below the categories of the upper section: subtotal, points needed
+/// for the bonus, difference from par, bonus and total.
+const UPPER_TOTAL_ROWS: usize = 5;
+/// Rows below the categories of the lower section: its total.
+const LOWER_TOTAL_ROWS: usize = 1;
+const TABLE_HEIGHT: u16 = (
+    // title and separator of each section
+    2 * 2
+    + category::CATEGORIES.len()
+    + UPPER_TOTAL_ROWS
+    + LOWER_TOTAL_ROWS
+    // blank line after each section
+    + 2
+    // grand total
+    + 1
+) as u16;
+const CONTENT_HEIGHT: u16 = if DICE_COLUMN_HEIGHT > TABLE_HEIGHT {
+    DICE_COLUMN_HEIGHT
+} else {
+    TABLE_HEIGHT
+};
+/// Rows of the odds panel: its title and a row for each category.
+const ODDS_HEIGHT: u16 = 1 + category::CATEGORIES.len() as u16;
+const TABLE_WIDTH: usize = 24;
+const PAR_NOTE_WIDTH: usize = 13;
+const ANIMATION_DURATION: Duration = Duration::from_millis(400);
+const ANIMATION_FRAME_INTERVAL: Duration = Duration::from_millis(50);
+const BOT_STEP_INTERVAL: Duration = Duration::from_millis(700);
+
+/// A game at the terminal, between players taking turns and bots.
+pub struct Game {
+    charset: Charset,
+    dice_set: DiceSet,
+    players: Vec<Player>,
+    current_player: usize,
+    roll_count: usize,
+    selected_category_index: Option<usize>,
+    dice_selected: [bool; DiceSet::NUM_DICE],
+    animation_enabled: bool,
+    animation: Option<Animation>,
+    /// Number of terminals the game is played at, each seating one of the
+    /// players when there are several.
+    screens: usize,
+    scorekeeper: bool,
+    sandbox: bool,
+    /// Whether hints, the odds of each category and the chances of winning
+    /// are shown
+    hints: bool,
+    focused_dice: usize,
+    /// Dice typed in since the turn started, in scorekeeper mode.
+    dice_entered: [bool; DiceSet::NUM_DICE],
+    odds: OddsTable,
+    /// Shared with the threads working out hints.
+    solver: Arc<OnceLock<Solver>>,
+    /// Chances of winning, with what they were worked out for.
+    win_chances: Option<(WinChancesKey, Vec<f64>)>,
+    hint_engine: Engine,
+    /// The hint engine built as a strategy, kept between hints.
+    hint_strategy: Option<Box<dyn Strategy>>,
+    hint: Option<String>,
+    bot_decision: Option<Decision>,
+    bot_next_step: Instant,
+    /// Whether the bot in turn is working out its move on another thread.
+    bot_thinking: bool,
+    hint_thinking: bool,
+    win_chances_thinking: bool,
+    /// Moves worked out on other threads, sent back to the game.
+    thoughts: (Sender<Thought>, Receiver<Thought>),
+    /// What the players at the terminals said, oldest first.
+    chat: Vec<String>,
+}
+
+struct Player {
+    name: Option<String>,
+    scoreboard: Scoreboard,
+    /// How the player decides when it's a bot
+    bot: Option<Box<dyn Strategy>>,
+}
+
+impl Player {
+    fn name(&self, index: usize) -> String {
+        match (&self.name, &self.bot) {
+            (Some(name), _) => name.clone(),
+            (None, Some(_)) => format!("Bot {}", index + 1),
+            (None, None) => format!("Player {}", index + 1),
+        }
+    }
+}
+
+pub struct GameBuilder {
+    charset: Charset,
+    animation: bool,
+    players: usize,
+    names: Vec<String>,
+    bots: Vec<Box<dyn Strategy>>,
+    hint_engine: Engine,
+    scorekeeper: bool,
+    sandbox: bool,
+    hints: bool,
+}
+
+impl Default for GameBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GameBuilder {
+    pub fn new() -> Self {
+        Self {
+            charset: Charset::Unicode,
+            animation: true,
+            players: 1,
+            names: Vec::new(),
+            bots: Vec::new(),
+            hint_engine: Engine::Solver(Objective::ExpectedScore),
+            scorekeeper: false,
+            sandbox: false,
+            hints: true,
+        }
+    }
+
+    pub fn build(self) -> Game {
+        let dice_set = if self.scorekeeper {
+            DiceSet::default()
+        } else {
+            rand::thread_rng().gen()
+        };
+
+        let mut names = self.names.into_iter();
+        Game {
+            charset: self.charset,
+            dice_set,
+            players: (0..self.players)
+                .map(|_| (names.next(), None))
+                .chain(self.bots.into_iter().map(|bot| (None, Some(bot))))
+                .map(|(name, bot)| Player {
+                    name,
+                    scoreboard: Scoreboard::new(),
+                    bot,
+                })
+                .collect(),
+            current_player: 0,
+            roll_count: 1,
+            selected_category_index: Some(0),
+            dice_selected: [false; DiceSet::NUM_DICE],
+            // there is nothing to roll when the dice are typed in
+            animation_enabled: self.animation && !self.scorekeeper && !self.sandbox,
+            animation: None,
+            screens: 1,
+            scorekeeper: self.scorekeeper,
+            sandbox: self.sandbox,
+            hints: self.hints,
+            focused_dice: 0,
+            dice_entered: [false; DiceSet::NUM_DICE],
+            odds: OddsTable::new(MAX_ROLLS - 1),
+            solver: Arc::new(OnceLock::new()),
+            win_chances: None,
+            hint_engine: self.hint_engine,
+            hint_strategy: None,
+            hint: None,
+            bot_decision: None,
+            bot_next_step: Instant::now(),
+            bot_thinking: false,
+            hint_thinking: false,
+            win_chances_thinking: false,
+            thoughts: crossbeam_channel::unbounded(),
+            chat: Vec::new(),
+        }
+    }
+
+    pub fn charset(mut self, charset: Charset) -> Self {
+        self.charset = charset;
+        self
+    }
+
+    pub fn animation(mut self, animation: bool) -> Self {
+        self.animation = animation;
+        self
+    }
+
+    pub fn players(mut self, players: usize) -> Self {
+        self.players = players;
+        self
+    }
+
+    /// Names of the players at the terminals, in order, in place of
+    /// "Player 1" and so on.
+    pub fn player_names(mut self, names: Vec<String>) -> Self {
+        self.names = names;
+        self
+    }
+
+    /// Adds a bot player after the players at the terminal.
+    pub fn bot(mut self, strategy: Box<dyn Strategy>) -> Self {
+        self.bots.push(strategy);
+        self
+    }
+
+    pub fn hint_engine(mut self, engine: Engine) -> Self {
+        self.hint_engine = engine;
+        self
+    }
+
+    pub fn scorekeeper(mut self, scorekeeper: bool) -> Self {
+        self.scorekeeper = scorekeeper;
+        self
+    }
+
+    pub fn sandbox(mut self, sandbox: bool) -> Self {
+        self.sandbox = sandbox;
+        self
+    }
+
+    /// Whether players may ask for hints and see the odds of each category
+    /// and their chances of winning.
+    pub fn hints(mut self, hints: bool) -> Self {
+        self.hints = hints;
+        self
+    }
+}
+
+/// Everything a bot or a hint goes by, taken from the game so the move can
+/// be worked out on another thread.
+#[derive(Clone, PartialEq)]
+struct Position {
+    dice_set: DiceSet,
+    roll: usize,
+    current_player: usize,
+    scoreboards: Vec<Scoreboard>,
+}
+
+impl Position {
+    fn scoreboard(&self) -> &Scoreboard {
+        &self.scoreboards[self.current_player]
+    }
+
+    /// Scoreboards of everyone but the current player.
+    fn opponents(&self) -> Vec<&Scoreboard> {
+        self.scoreboards
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != self.current_player)
+            .map(|(_, scoreboard)| scoreboard)
+            .collect()
+    }
+
+    fn turn<'a>(&'a self, opponents: &'a [&'a Scoreboard]) -> Turn<'a> {
+        Turn {
+            dice_set: &self.dice_set,
+            roll: self.roll,
+            scoreboard: self.scoreboard(),
+            opponents,
+        }
+    }
+
+    /// Asks `strategy` for its next move, scoring in the first available
+    /// category instead of a move the rules don't allow.
+    fn decide(&self, strategy: &mut dyn Strategy) -> Decision {
+        let opponents = self.opponents();
+        let turn = self.turn(&opponents);
+        let decision = strategy.decide(&turn, &mut rand::thread_rng());
+        if turn.allows(decision) {
+            decision
+        } else {
+            Decision::Score(turn.first_available())
+        }
+    }
+
+    /// Works out the move `engine` would make, with an estimate of the final
+    /// score when it gives one. Engines playing as a strategy are built into
+    /// `strategy` the first time, to be used again for later hints.
+    fn hint(
+        &self,
+        engine: Engine,
+        solver: &OnceLock<Solver>,
+        strategy: &mut Option<Box<dyn Strategy>>,
+    ) -> (Decision, Option<rollout::Estimate>) {
+        let rolls_left = MAX_ROLLS - self.roll;
+        let objective = match engine {
+            Engine::Solver(objective) => objective,
+            Engine::Rollout(rollout) => {
+                let estimate = rollout.decide(
+                    self.scoreboard(),
+                    &self.dice_set,
+                    rolls_left,
+                    &mut rand::thread_rng(),
+                );
+                return (estimate.decision, Some(estimate));
+            }
+            Engine::Policy(_) | Engine::Heuristic(_) => {
+                let strategy = strategy.get_or_insert_with(|| engine.strategy());
+                return (self.decide(strategy.as_mut()), None);
+            }
+        };
+
+        let decision = solver
+            .get_or_init(Solver::new)
+            .decide(
+                self.scoreboard(),
+                &self.opponents(),
+                &self.dice_set,
+                rolls_left,
+                objective,
+            )
+            .unwrap_or_else(|| {
+                let opponents = self.opponents();
+                Decision::Score(self.turn(&opponents).first_available())
+            });
+        (decision, None)
+    }
+}
+
+/// A move worked out on another thread, for the position it was asked for.
+enum Thought {
+    /// The bot in turn, handed back with its move.
+    Bot(Box<dyn Strategy>, Position, Decision),
+    /// A hint, with the hint strategy handed back.
+    Hint {
+        position: Position,
+        decision: Decision,
+        estimate: Option<rollout::Estimate>,
+        strategy: Option<Box<dyn Strategy>>,
+    },
+    /// Each player's chance of winning, for what they were worked out for.
+    WinChances(WinChancesKey, Vec<f64>),
+}
+
+/// What the chances of winning depend on: the scoreboards, and the dice and
+/// rolls left of the current player when they are known.
+#[derive(PartialEq)]
+struct WinChancesKey {
+    scoreboards: Vec<Scoreboard>,
+    current_player: usize,
+    roll: Option<(DiceSet, usize)>,
+}
+
+/// Random faces shown in place of rolled dice until `end`.
+struct Animation {
+    faces: DiceSet,
+    rolling: [bool; DiceSet::NUM_DICE],
+    end: Instant,
+}
+
+/// A terminal the game is shown on: the one it runs in, or one reached
+/// over the network.
+pub struct Screen {
+    out: Box<dyn Write + Send>,
+    height: u16,
+    help: Option<HelpScreen>,
+    /// Chat message being typed, if the input line is open.
+    chat_input: Option<String>,
+    /// Chat messages scrolled back by.
+    chat_scroll: usize,
+}
+
+impl Screen {
+    /// A terminal `height` rows high, drawn on through `out`.
+    pub fn new(out: Box<dyn Write + Send>, height: u16) -> Self {
+        Self {
+            out,
+            height,
+            help: None,
+            chat_input: None,
+            chat_scroll: 0,
+        }
+    }
+}
+
+impl Game {
+    /// Plays at the terminal the program runs in.
+    pub fn run(self) -> Result<()> {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        std::thread::spawn(move || loop {
+            if let Ok(event) = event::read() {
+                let _ = tx.send((0, event));
+            }
+        });
+
+        let (_, height) = terminal::size()?;
+        terminal::enable_raw_mode()?;
+        let result = self.play(vec![Screen::new(Box::new(io::stdout()), height)], rx);
+        terminal::disable_raw_mode()?;
+
+        result
+    }
+
+    /// Plays at `screens`, with `events` coming from the terminal of the
+    /// screen at the given index. With several screens, the players at the
+    /// terminals sit one at each, in order, and may only move in their own
+    /// turn, while any of them may chat. Quitting at any of them ends the
+    /// game.
+    pub fn play(
+        mut self,
+        mut screens: Vec<Screen>,
+        events: Receiver<(usize, Event)>,
+    ) -> Result<()> {
+        let ticker = crossbeam_channel::tick(ANIMATION_FRAME_INTERVAL);
+
+        self.screens = screens.len();
+        for screen in &mut screens {
+            setup_terminal(&mut screen.out)?;
+        }
+
+        self.start_animation([true; DiceSet::NUM_DICE]);
+
+        let thoughts = self.thoughts.1.clone();
+        loop {
+            self.think_about_win_chances();
+            for (i, screen) in screens.iter_mut().enumerate() {
+                match &screen.help {
+                    Some(help) => help.draw(&mut screen.out, screen.height)?,
+                    None => {
+                        self.draw(&mut screen.out, i)?;
+                        self.draw_chat(
+                            &mut screen.out,
+                            screen.chat_input.as_deref(),
+                            screen.chat_scroll,
+                        )?;
+                    }
+                }
+                screen.out.flush()?;
+            }
+
+            let ticks = if self.animation.is_some() || self.is_bot_turn() {
+                ticker.clone()
+            } else {
+                crossbeam_channel::never()
+            };
+            let (i, event) = select! {
+                recv(events) -> event => event?,
+                recv(thoughts) -> thought => {
+                    self.on_thought(thought?);
+                    continue;
+                }
+                recv(ticks) -> _ => {
+                    self.on_tick();
+                    continue;
+                }
+            };
+            let screen = match screens.get_mut(i) {
+                Some(screen) => screen,
+                None => continue,
+            };
+
+            if let Event::Resize(_, height) = event {
+                screen.height = height;
+                crossterm::queue!(screen.out, terminal::Clear(terminal::ClearType::All))?;
+            }
+
+            if let Event::Key(key) = event {
+                // typing goes on while the dice roll
+                if let Some(input) = &mut screen.chat_input {
+                    match (key.modifiers, key.code) {
+                        (KeyModifiers::CONTROL, KeyCode::Char('c')) => break,
+                        (_, KeyCode::Esc) => screen.chat_input = None,
+                        (_, KeyCode::Enter) => {
+                            let text = std::mem::take(input);
+                            screen.chat_input = None;
+                            screen.chat_scroll = 0;
+                            self.say(i, text.trim());
+                        }
+                        (_, KeyCode::Backspace) => {
+                            input.pop();
+                        }
+                        (_, KeyCode::Char(c))
+                            if !c.is_control() && input.chars().count() < MAX_CHAT_INPUT_LEN =>
+                        {
+                            input.push(c)
+                        }
+                        _ => (),
+                    }
+                    continue;
+                }
+
+                if self.animation.is_some() {
+                    // any key skips the animation
+                    self.animation = None;
+                    continue;
+                }
+
+                if let Some(help) = &mut screen.help {
+                    match (key.modifiers, key.code) {
+                        (KeyModifiers::CONTROL, KeyCode::Char('c')) => break,
+                        (_, KeyCode::Esc)
+                        | (_, KeyCode::F(1))
+                        | (_, KeyCode::Char('?'))
+                        | (_, KeyCode::Char('q')) => {
+                            screen.help = None;
+                            crossterm::queue!(
+                                screen.out,
+                                terminal::Clear(terminal::ClearType::All)
+                            )?;
+                        }
+                        (_, code) => help.on_key(code, screen.height),
+                    }
+                    continue;
+                }
+
+                self.hint = None;
+
+                match (key.modifiers, key.code) {
+                    (_, KeyCode::Esc)
+                    | (KeyModifiers::CONTROL, KeyCode::Char('c'))
+                    | (_, KeyCode::Char('q')) => break,
+                    (_, KeyCode::F(1)) | (_, KeyCode::Char('?')) => {
+                        screen.help = Some(HelpScreen::new(self.charset));
+                    }
+                    // chatting is open to everyone at any time
+                    (_, KeyCode::Char('t')) if self.screens > 1 => {
+                        screen.chat_input = Some(String::new());
+                    }
+                    (_, KeyCode::Char(c)) if self.screens > 1 && emote(c).is_some() => {
+                        screen.chat_scroll = 0;
+                        self.say(i, emote(c).unwrap());
+                    }
+                    (_, KeyCode::PageUp) if self.screens > 1 => {
+                        let max_scroll = self.chat.len().saturating_sub(CHAT_LINES);
+                        screen.chat_scroll = (screen.chat_scroll + CHAT_LINES).min(max_scroll);
+                    }
+                    (_, KeyCode::PageDown) if self.screens > 1 => {
+                        screen.chat_scroll = screen.chat_scroll.saturating_sub(CHAT_LINES);
+                    }
+                    // the bot plays on its own
+                    _ if self.is_bot_turn() => (),
+                    // and the other players at their own terminals
+                    _ if !self.plays_at(i) => (),
+                    (_, KeyCode::Char('h')) if !self.scorekeeper && self.hints => self.on_h_key(),
+                    (_, KeyCode::Up) | (_, KeyCode::Char('k')) | (_, KeyCode::Char('w')) => {
+                        self.on_up();
+                    }
+                    (_, KeyCode::Down) | (_, KeyCode::Char('j')) | (_, KeyCode::Char('s')) => {
+                        self.on_down();
+                    }
+                    (_, KeyCode::Home) => self.on_home(),
+                    (_, KeyCode::End) => self.on_end(),
+                    (_, KeyCode::Enter) | (_, KeyCode::Char(' ')) => self.on_enter(),
+                    (_, KeyCode::Tab) if self.dice_are_editable() => self.on_tab(),
+                    (_, KeyCode::BackTab) if self.dice_are_editable() => self.on_back_tab(),
+                    (_, KeyCode::Left) | (_, KeyCode::Char('-')) if self.dice_are_editable() => {
+                        self.on_left()
+                    }
+                    (_, KeyCode::Right) | (_, KeyCode::Char('+')) | (_, KeyCode::Char('='))
+                        if self.dice_are_editable() =>
+                    {
+                        self.on_right()
+                    }
+                    (_, KeyCode::Char(c)) if self.dice_are_editable() && c.is_ascii_digit() => {
+                        self.on_face_key(c)
+                    }
+                    (_, KeyCode::Char('[')) if self.sandbox => self.on_left_bracket(),
+                    (_, KeyCode::Char(']')) if self.sandbox => self.on_right_bracket(),
+                    (_, KeyCode::Backspace) | (_, KeyCode::Delete) if self.sandbox => {
+                        self.on_delete()
+                    }
+                    (_, KeyCode::Char('r')) => self.on_r_key(),
+                    (_, KeyCode::Char(c)) if c.is_ascii_digit() => self.on_number_key(c),
+                    _ => (),
+                }
+            }
+
+            if !self.sandbox && self.game_is_finished() {
+                break;
+            }
+        }
+
+        for (i, screen) in screens.iter_mut().enumerate() {
+            self.draw(&mut screen.out, i)?;
+            let chat_height = if self.screens > 1 {
+                self.draw_chat(&mut screen.out, None, 0)?;
+                CHAT_HEIGHT
+                    // margin
+                    + 1
+            } else {
+                0
+            };
+            crossterm::queue!(
+                screen.out,
+                cursor::MoveTo(
+                    0,
+                    // roll count
+                    1
+                    // margin
+                    + 1
+                    // main content
+                    + CONTENT_HEIGHT
+                    // margin
+                    + 1
+                    // message
+                    + MAX_MESSAGE_LINES as u16
+                    // margin
+                    + 1
+                    + chat_height
+                )
+            )?;
+            cleanup_terminal(&mut screen.out)?;
+            screen.out.flush()?;
+        }
+
+        Ok(())
+    }
+
+    fn on_up(&mut self) {
+        const N: usize = category::CATEGORIES.len();
+        let i = (self.selected_category_index.unwrap() + N - 1) % N;
+        self.selected_category_index = Some(i);
+        self.select_prev_available_category();
+    }
+
+    fn on_down(&mut self) {
+        let i = (self.selected_category_index.unwrap() + 1) % category::CATEGORIES.len();
+        self.selected_category_index = Some(i);
+        self.select_next_available_category();
+    }
+
+    fn on_home(&mut self) {
+        self.selected_category_index = Some(0);
+        self.select_next_available_category();
+    }
+
+    fn on_end(&mut self) {
+        self.selected_category_index = Some(category::CATEGORIES.len() - 1);
+        self.select_prev_available_category();
+    }
+
+    fn select_prev_available_category(&mut self) {
+        const N: usize = category::CATEGORIES.len();
+
+        while !self
+            .category_is_selectable(category::CATEGORIES[self.selected_category_index.unwrap()])
+        {
+            let i = (self.selected_category_index.unwrap() + N - 1) % N;
+            self.selected_category_index = Some(i);
+        }
+    }
+
+    fn select_next_available_category(&mut self) {
+        while !self
+            .category_is_selectable(category::CATEGORIES[self.selected_category_index.unwrap()])
+        {
+            let i = (self.selected_category_index.unwrap() + 1) % category::CATEGORIES.len();
+            self.selected_category_index = Some(i);
+        }
+    }
+
+    fn category_is_selectable(&self, category: category::Category) -> bool {
+        // any entry of the scoreboard can be overwritten in the sandbox
+        self.sandbox
+            || self
+                .scoreboard()
+                .category_is_available(category, &self.dice_set)
+    }
+
+    fn on_enter(&mut self) {
+        let category = category::CATEGORIES[self.selected_category_index.unwrap()];
+
+        if self.sandbox {
+            let scoreboard = &mut self.players[self.current_player].scoreboard;
+            if !scoreboard.category_is_available(category, &self.dice_set) {
+                scoreboard.clear_category(category);
+            }
+            scoreboard.choose_category(category, &self.dice_set);
+            return;
+        }
+
+        if self.scorekeeper && !self.dice_entered.iter().all(|x| *x) {
+            self.hint = Some("Type in the rolled faces first".to_string());
+            return;
+        }
+
+        self.players[self.current_player]
+            .scoreboard
+            .choose_category(category, &self.dice_set);
+
+        self.dice_selected = [false; DiceSet::NUM_DICE];
+
+        if self.game_is_finished() {
+            self.selected_category_index = None;
+        } else {
+            // a bonus five of a kind takes a turn without filling a category,
+            // so players can finish at different times
+            loop {
+                self.current_player = (self.current_player + 1) % self.players.len();
+                if !self.scoreboard().game_is_finished() {
+                    break;
+                }
+            }
+            self.bot_next_step = Instant::now() + BOT_STEP_INTERVAL;
+            self.roll_count = 1;
+            if self.scorekeeper {
+                // the next player rolls their own dice
+                self.focused_dice = 0;
+                self.dice_entered = [false; DiceSet::NUM_DICE];
+            } else {
+                self.dice_set = rand::thread_rng().gen();
+            }
+            self.select_next_available_category();
+            self.start_animation([true; DiceSet::NUM_DICE]);
+        }
+    }
+
+    fn on_r_key(&mut self) {
+        if self.dice_are_editable()
+            || self.roll_count >= MAX_ROLLS
+            || self.dice_selected.iter().all(|x| !x)
+        {
+            return;
+        }
+
+        for (dice, selected) in self.dice_set.0.iter_mut().zip(self.dice_selected.iter()) {
+            if *selected {
+                *dice = rand::thread_rng().gen();
+            }
+        }
+
+        self.roll_count += 1;
+        self.select_next_available_category();
+        self.start_animation(self.dice_selected);
+        self.dice_selected = [false; DiceSet::NUM_DICE];
+    }
+
+    fn on_tab(&mut self) {
+        self.focused_dice = (self.focused_dice + 1) % DiceSet::NUM_DICE;
+    }
+
+    fn on_back_tab(&mut self) {
+        self.focused_dice = (self.focused_dice + DiceSet::NUM_DICE - 1) % DiceSet::NUM_DICE;
+    }
+
+    fn on_left(&mut self) {
+        let dice = &mut self.dice_set.0[self.focused_dice];
+        *dice = dice.pred();
+        self.dice_entered[self.focused_dice] = true;
+        self.select_next_available_category();
+    }
+
+    fn on_right(&mut self) {
+        let dice = &mut self.dice_set.0[self.focused_dice];
+        *dice = dice.succ();
+        self.dice_entered[self.focused_dice] = true;
+        self.select_next_available_category();
+    }
+
+    fn on_left_bracket(&mut self) {
+        self.roll_count = (self.roll_count - 1).max(1);
+    }
+
+    fn on_right_bracket(&mut self) {
+        self.roll_count = (self.roll_count + 1).min(MAX_ROLLS);
+    }
+
+    fn on_delete(&mut self) {
+        let category = category::CATEGORIES[self.selected_category_index.unwrap()];
+        self.players[self.current_player]
+            .scoreboard
+            .clear_category(category);
+    }
+
+    fn on_face_key(&mut self, c: char) {
+        if let Some(face) = parse_face(c) {
+            self.dice_set.0[self.focused_dice] = face;
+            self.dice_entered[self.focused_dice] = true;
+            self.on_tab();
+            self.select_next_available_category();
+        }
+    }
+
+    fn on_tick(&mut self) {
+        if let Some(animation) = &mut self.animation {
+            if Instant::now() >= animation.end {
+                self.animation = None;
+            } else {
+                animation.faces = rand::thread_rng().gen();
+            }
+        } else if self.is_bot_turn() && Instant::now() >= self.bot_next_step {
+            self.bot_next_step = Instant::now() + BOT_STEP_INTERVAL;
+            self.on_bot_step();
+        }
+    }
+
+    /// Shows what the bot is going to do first, and does it on the next step.
+    fn on_bot_step(&mut self) {
+        match self.bot_decision.take() {
+            Some(Decision::Reroll(_)) => self.on_r_key(),
+            Some(Decision::Score(_)) => self.on_enter(),
+            None if self.bot_thinking => (),
+            None => {
+                let mut bot = self.players[self.current_player].bot.take().unwrap();
+                let position = self.position();
+                let thoughts = self.thoughts.0.clone();
+                self.bot_thinking = true;
+                thread::spawn(move || {
+                    let decision = position.decide(bot.as_mut());
+                    // the game may be over by now
+                    let _ = thoughts.send(Thought::Bot(bot, position, decision));
+                });
+            }
+        }
+    }
+
+    fn on_h_key(&mut self) {
+        // a sandbox game goes on after it is finished, with nothing to hint
+        if self.hint_thinking || self.game_is_finished() {
+            return;
+        }
+        let position = self.position();
+        let engine = self.hint_engine;
+        let solver = self.solver.clone();
+        let mut strategy = self.hint_strategy.take();
+        let thoughts = self.thoughts.0.clone();
+        self.hint_thinking = true;
+        self.hint = Some("Working out a hint...".to_string());
+        thread::spawn(move || {
+            let (decision, estimate) = position.hint(engine, &solver, &mut strategy);
+            let _ = thoughts.send(Thought::Hint {
+                position,
+                decision,
+                estimate,
+                strategy,
+            });
+        });
+    }
+
+    /// Takes in a move worked out on another thread, unless the game has
+    /// moved on since it was asked for.
+    fn on_thought(&mut self, thought: Thought) {
+        match thought {
+            Thought::Bot(bot, position, decision) => {
+                self.players[position.current_player].bot = Some(bot);
+                self.bot_thinking = false;
+                if position != self.position() {
+                    return;
+                }
+
+                match &decision {
+                    Decision::Reroll(rerolled) => self.dice_selected = *rerolled,
+                    Decision::Score(category) => {
+                        self.selected_category_index = Some(category_index(*category));
+                    }
+                }
+                self.bot_decision = Some(decision);
+                self.bot_next_step = Instant::now() + BOT_STEP_INTERVAL;
+            }
+            Thought::Hint {
+                position,
+                decision,
+                estimate,
+                strategy,
+            } => {
+                self.hint_strategy = strategy;
+                self.hint_thinking = false;
+                if position == self.position() {
+                    self.hint = Some(self.hint_text(decision, estimate));
+                }
+            }
+            Thought::WinChances(key, chances) => {
+                self.win_chances = Some((key, chances));
+                self.win_chances_thinking = false;
+            }
+        }
+    }
+
+    fn hint_text(&self, decision: Decision, estimate: Option<rollout::Estimate>) -> String {
+        let mut hint = match decision {
+            Decision::Reroll(rerolled) => {
+                let dice: Vec<_> = (1..=DiceSet::NUM_DICE)
+                    .zip(rerolled.iter())
+                    .filter(|(_, rerolled)| **rerolled)
+                    .map(|(i, _)| i.to_string())
+                    .collect();
+                format!("Hint: re-roll dice {}", dice.join(", "))
+            }
+            Decision::Score(category) => {
+                format!("Hint: score {}", category.label(self.charset))
+            }
+        };
+        if let Some(estimate) = estimate {
+            let plus_minus = match self.charset {
+                Charset::Unicode => "±",
+                Charset::Ascii => "+/-",
+            };
+            hint += &format!(
+                " (final score {:.1} {} {:.1})",
+                estimate.mean, plus_minus, estimate.margin
+            );
+        }
+        hint
+    }
+
+    fn position(&self) -> Position {
+        Position {
+            dice_set: self.dice_set,
+            roll: self.roll_count,
+            current_player: self.current_player,
+            scoreboards: self
+                .players
+                .iter()
+                .map(|player| player.scoreboard.clone())
+                .collect(),
+        }
+    }
+
+    fn start_animation(&mut self, rolling: [bool; DiceSet::NUM_DICE]) {
+        if self.animation_enabled {
+            self.animation = Some(Animation {
+                faces: rand::thread_rng().gen(),
+                rolling,
+                end: Instant::now() + ANIMATION_DURATION,
+            });
+        }
+    }
+
+    fn on_number_key(&mut self, c: char) {
+        if self.roll_count >= MAX_ROLLS {
+            return;
+        }
+
+        if let Some(d) = parse_dice_number(c) {
+            self.dice_selected[d] ^= true;
+        }
+    }
+
+    fn scoreboard(&self) -> &Scoreboard {
+        &self.players[self.current_player].scoreboard
+    }
+
+    fn dice_are_editable(&self) -> bool {
+        self.scorekeeper || self.sandbox
+    }
+
+    fn game_is_finished(&self) -> bool {
+        self.players
+            .iter()
+            .all(|player| player.scoreboard.game_is_finished())
+    }
+
+    fn is_bot_turn(&self) -> bool {
+        !self.game_is_finished()
+            && (self.bot_thinking || self.players[self.current_player].bot.is_some())
+    }
+
+    /// Adds what the player at the terminal of `screen` said to the chat.
+    fn say(&mut self, screen: usize, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        self.chat
+            .push(format!("{}: {}", self.players[screen].name(screen), text));
+        if self.chat.len() > CHAT_SCROLLBACK {
+            self.chat.remove(0);
+        }
+    }
+
+    /// Whether the current player plays at the terminal of `screen`.
+    fn plays_at(&self, screen: usize) -> bool {
+        self.screens == 1 || screen == self.current_player
+    }
+
+    fn draw<W: io::Write>(&self, w: &mut W, screen: usize) -> Result<()> {
+        let mut text = Vec::new();
+        if self.scorekeeper {
+            text.push("Scorekeeper".to_string());
+        }
+        if self.sandbox {
+            text.push("Sandbox".to_string());
+        }
+        if self.players.len() > 1 {
+            text.push(format!(
+                "{} / {}",
+                self.players[self.current_player].name(self.current_player),
+                self.players.len()
+            ));
+        }
+        if !self.scorekeeper {
+            text.push(format!("Roll {} / {}", self.roll_count, MAX_ROLLS));
+        }
+        text.push("F1 or ?: help".to_string());
+        crossterm::queue!(
+            w,
+            cursor::MoveTo(0, 0),
+            terminal::Clear(terminal::ClearType::CurrentLine),
+            style::Print(text.join("    "))
+        )?;
+
+        self.draw_content(w, 0, 2)?;
+
+        const MESSAGE_Y: u16 = 3 + CONTENT_HEIGHT;
+        crossterm::queue!(w, cursor::MoveTo(0, MESSAGE_Y),)?;
+
+        let mut text = vec!["Enter:       choose a scoring category"];
+        if self.sandbox {
+            text[0] = "Enter:       score selected category    Del: clear it";
+            text.push("1-6, +/-:    set the faces    Tab: next die");
+            text.push("[ / ]:       change the roll number");
+        } else if self.scorekeeper {
+            text.push("1-6:         type the rolled faces");
+            text.push("Tab:         next die    Left/Right: change its face");
+        } else if self.roll_count < MAX_ROLLS {
+            text.push("Number keys: mark dice to be re-rolled");
+            if self.dice_selected.iter().any(|x| *x) {
+                text.push("R:           roll marked dice");
+            }
+        }
+        let playing_text;
+        if self.is_bot_turn() || (!self.game_is_finished() && !self.plays_at(screen)) {
+            playing_text = format!(
+                "{} is playing...",
+                self.players[self.current_player].name(self.current_player)
+            );
+            text = vec![&playing_text];
+        }
+        if let Some(hint) = &self.hint {
+            text.push(hint);
+        }
+
+        for (line, y) in text
+            .iter()
+            .chain(std::iter::repeat(&""))
+            .take(MAX_MESSAGE_LINES)
+            .zip(MESSAGE_Y..)
+        {
+            crossterm::queue!(
+                w,
+                cursor::MoveTo(0, y),
+                terminal::Clear(terminal::ClearType::CurrentLine),
+                style::Print(line)
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Draws the chat of a game played at several screens below the message
+    /// area, scrolled back by `scroll` messages, with the input line if a
+    /// message is being typed.
+    fn draw_chat<W: io::Write>(&self, w: &mut W, input: Option<&str>, scroll: usize) -> Result<()> {
+        if self.screens == 1 {
+            return Ok(());
+        }
+        const CHAT_Y: u16 = 3 + CONTENT_HEIGHT + MAX_MESSAGE_LINES as u16 + 1;
+
+        let mut title = "Chat    T: talk".to_string();
+        for (key, text) in EMOTES.iter() {
+            title += &format!("    {}: {}", key.to_ascii_uppercase(), text);
+        }
+        if self.chat.len() > CHAT_LINES {
+            title += "    PgUp/PgDn: scroll";
+        }
+        crossterm::queue!(
+            w,
+            cursor::MoveTo(0, CHAT_Y),
+            terminal::Clear(terminal::ClearType::CurrentLine),
+            style::Print(title)
+        )?;
+
+        let end = self.chat.len() - scroll.min(self.chat.len());
+        let start = end.saturating_sub(CHAT_LINES);
+        for (line, y) in self.chat[start..end]
+            .iter()
+            .map(String::as_str)
+            .chain(std::iter::repeat(""))
+            .take(CHAT_LINES)
+            .zip(CHAT_Y + 1..)
+        {
+            crossterm::queue!(
+                w,
+                cursor::MoveTo(0, y),
+                terminal::Clear(terminal::ClearType::CurrentLine),
+                style::Print(format!("  {}", line))
+            )?;
+        }
+
+        crossterm::queue!(
+            w,
+            cursor::MoveTo(0, CHAT_Y + CHAT_HEIGHT - 1),
+            terminal::Clear(terminal::ClearType::CurrentLine)
+        )?;
+        if let Some(input) = input {
+            crossterm::queue!(
+                w,
+                style::Print(format!("Say: {}_    Enter: send    Esc: cancel", input))
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn draw_content<W: io::Write>(&self, w: &mut W, x: u16, y: u16) -> Result<()> {
+        let dice_num_x = x
+            // left margin
+            +2;
+        let dice_x = dice_num_x
+            // width of dice number
+            + 1
+            // margin
+            + 2;
+
+        for (i, dice) in self.dice_set.0.iter().enumerate() {
+            let dice = match &self.animation {
+                Some(animation) if animation.rolling[i] => &animation.faces.0[i],
+                _ => dice,
+            };
+
+            crossterm::queue!(
+                w,
+                cursor::MoveTo(dice_num_x, y + Dice::HEIGHT / 2 + Dice::HEIGHT * i as u16),
+                style::Print(i + 1)
+            )?;
+
+            if self.dice_selected[i] || (self.dice_are_editable() && i == self.focused_dice) {
+                dice.draw_styled(
+                    w,
+                    dice_x,
+                    y + Dice::HEIGHT * i as u16,
+                    self.charset,
+                    style::ContentStyle::new()
+                        .foreground(style::Color::Black)
+                        .background(style::Color::Yellow),
+                )?;
+            } else {
+                dice.draw(w, dice_x, y + Dice::HEIGHT * i as u16, self.charset)?;
+            }
+        }
+
+        let table_x = dice_x
+            + Dice::WIDTH
+            // margin
+            + 4;
+        self.draw_table(&mut *w, table_x, y)?;
+
+        let side_x = table_x
+            + TABLE_WIDTH as u16
+            // margin
+            + 1
+            + PAR_NOTE_WIDTH as u16
+            // margin
+            + 2;
+        let mut side_y = y;
+        if self.players.len() > 1 {
+            // title and margin
+            let rows = (self.players.len() as u16).min(CONTENT_HEIGHT - 2);
+            self.draw_standings(&mut *w, side_x, side_y, rows as usize)?;
+            side_y += rows
+                // title
+                + 1
+                // margin
+                + 1;
+        }
+        // the odds are left out rather than cut short when many players
+        // take up the room
+        if !self.scorekeeper && self.hints && side_y + ODDS_HEIGHT <= y + CONTENT_HEIGHT {
+            self.draw_odds(w, side_x, side_y)?;
+        }
+
+        Ok(())
+    }
+
+    fn draw_table<W: io::Write>(&self, w: W, x: u16, y: u16) -> Result<()> {
+        use table_printer::{TablePrinter, TablePrinterBuilder};
+
+        let mut printer = TablePrinterBuilder::new(w)
+            .x(x)
+            .y(y)
+            .width(TABLE_WIDTH)
+            .key_width(19)
+            .value_width(3)
+            .note_width(PAR_NOTE_WIDTH)
+            .charset(self.charset)
+            .build();
+
+        let print_section =
+            |printer: &mut TablePrinter<W>, section: &[category::Category], offset| -> Result<()> {
+                for (i, category) in section.iter().enumerate() {
+                    let label = category.label(self.charset);
+                    let filled = self.scoreboard().category_score(*category);
+                    let preview = if self.animation.is_none()
+                        && self
+                            .scoreboard()
+                            .category_is_available(*category, &self.dice_set)
+                    {
+                        Some(self.dice_set.score(*category))
+                    } else {
+                        None
+                    };
+
+                    let note = match (category.face(), filled, preview) {
+                        (Some(face), None, Some(score)) => par_note(score, face),
+                        // keep the recorded score in sight next to the bonus
+                        (_, Some(score), Some(_)) => format!("{} so far", score),
+                        (_, Some(_), None) if self.sandbox => {
+                            format!("would be {}", self.dice_set.score(*category))
+                        }
+                        _ => String::new(),
+                    };
+                    printer.note(note)?;
+
+                    let selected = self
+                        .selected_category_index
+                        .map(|selected| i + offset == selected)
+                        .unwrap_or(false);
+
+                    match (filled, preview) {
+                        // bonus 5 of a Kind is added to the existing score
+                        (Some(_), Some(score)) if selected => {
+                            printer.selected_item(label, format!("+{}", score))?
+                        }
+                        (Some(_), Some(score)) => {
+                            printer.highlighted_item(label, format!("+{}", score))?
+                        }
+                        (Some(score), None) => printer.item(label, score)?,
+                        (None, Some(0)) if selected => printer.selected_dimmed_item(label, 0)?,
+                        (None, Some(0)) => printer.dimmed_item(label, 0)?,
+                        (None, Some(score)) if selected => printer.selected_item(label, score)?,
+                        (None, Some(score)) => printer.highlighted_item(label, score)?,
+                        (None, None) => printer.item(label, "")?,
+                    };
+                }
+
+                Ok(())
+            };
+
+        printer.section("Upper Section")?.indent()?;
+        print_section(&mut printer, &category::UPPER_SECTION, 0)?;
+        let basic_total = self.scoreboard().basic_total();
+        let bonus_status = if basic_total >= scoreboard::UPPER_SECTION_BONUS_THRESHOLD {
+            "secured"
+        } else if self.scoreboard().upper_section_bonus_is_achievable() {
+            "achievable"
+        } else {
+            "out of reach"
+        };
+        printer
+            .separator()?
+            .item("Subtotal", basic_total)?
+            .item(
+                "Needed for Bonus",
+                scoreboard::UPPER_SECTION_BONUS_THRESHOLD.saturating_sub(basic_total),
+            )?
+            .item(
+                "vs. Par",
+                format!("{:+}", self.scoreboard().upper_section_par_difference()),
+            )?
+            .note(bonus_status)?
+            .item("Bonus if > 62", self.scoreboard().upper_section_bonus())?
+            .item("Total", self.scoreboard().upper_total())?
+            .unindent()?
+            .blank()?;
+
+        printer.section("Lower Section")?.indent()?;
+        print_section(
+            &mut printer,
+            &category::LOWER_SECTION,
+            category::UPPER_SECTION.len(),
+        )?;
+        printer
+            .separator()?
+            .item("Total", self.scoreboard().lower_total())?
+            .unindent()?
+            .blank()?;
+
+        printer.item("Grand Total", self.scoreboard().grand_total())?;
+        debug_assert_eq!(printer.y(), y + TABLE_HEIGHT);
+
+        Ok(())
+    }
+
+    fn draw_odds<W: io::Write>(&self, w: W, x: u16, y: u16) -> Result<()> {
+        use table_printer::TablePrinterBuilder;
+
+        let mut printer = TablePrinterBuilder::new(w)
+            .x(x)
+            .y(y)
+            .width(21)
+            .key_width(15)
+            .value_width(6)
+            .charset(self.charset)
+            .build();
+
+        let title = if self.roll_count >= MAX_ROLLS {
+            "Chance to Score"
+        } else if self.dice_selected.iter().any(|x| *x) {
+            "Chance Rolling Marked"
+        } else {
+            // nothing is rolled unless dice are marked, so this is the
+            // chance with the best dice marked from now on
+            "Chance w/ Best Holds"
+        };
+        printer.section(format!("{:21}", title))?;
+        for category in &category::CATEGORIES {
+            let label = category.label(self.charset);
+            if !self.scoreboard().category_is_open(*category) {
+                // clear rows left over from categories scored earlier
+                printer.item("", "")?;
+            } else if self.animation.is_some() {
+                printer.item(label, "")?;
+            } else {
+                let chance = self.odds.chance(
+                    *category,
+                    &self.dice_set,
+                    &self.dice_selected,
+                    MAX_ROLLS - self.roll_count,
+                );
+                printer.item(label, format!("{:.1}%", chance * 100.0))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Works out each player's chance of winning on another thread once a
+    /// category is scored or the dice land, and not while they are rolling.
+    fn think_about_win_chances(&mut self) {
+        if !self.hints || self.win_chances_thinking || self.animation.is_some() {
+            return;
+        }
+
+        // typed in dice aren't known until all of them are
+        let roll_known = !self.scorekeeper || self.dice_entered == [true; DiceSet::NUM_DICE];
+        let key = WinChancesKey {
+            scoreboards: self
+                .players
+                .iter()
+                .map(|player| player.scoreboard.clone())
+                .collect(),
+            current_player: self.current_player,
+            roll: Some((self.dice_set, MAX_ROLLS - self.roll_count)).filter(|_| roll_known),
+        };
+        if matches!(&self.win_chances, Some((cached, _)) if *cached == key) {
+            return;
+        }
+
+        let solver = self.solver.clone();
+        let thoughts = self.thoughts.0.clone();
+        self.win_chances_thinking = true;
+        thread::spawn(move || {
+            let solver = solver.get_or_init(Solver::new);
+            let scoreboards: Vec<_> = key.scoreboards.iter().collect();
+            let chances = match key.roll {
+                Some((dice_set, rolls_left)) => solver.win_chances_during_turn(
+                    &scoreboards,
+                    key.current_player,
+                    &dice_set,
+                    rolls_left,
+                ),
+                None => solver.win_chances(&scoreboards),
+            };
+            let _ = thoughts.send(Thought::WinChances(key, chances));
+        });
+    }
+
+    /// Draws the standings of the first `rows` players.
+    fn draw_standings<W: io::Write>(&self, w: W, x: u16, y: u16, rows: usize) -> Result<()> {
+        use table_printer::TablePrinterBuilder;
+
+        let mut printer = TablePrinterBuilder::new(w)
+            .x(x)
+            .y(y)
+            .width(16)
+            .key_width(12)
+            .value_width(4)
+            .note_width(4)
+            .charset(self.charset)
+            .build();
+
+        // the last chances worked out, until newer ones come in
+        let win_chances = match &self.win_chances {
+            Some((_, chances)) if self.hints => chances.as_slice(),
+            _ => &[],
+        };
+        if self.hints {
+            printer.note(" Win")?;
+        }
+
+        printer.section("Standings")?.indent()?;
+        for (i, player) in self.players.iter().enumerate().take(rows) {
+            if let Some(chance) = win_chances.get(i) {
+                printer.note(format!("{:>3.0}%", chance * 100.0))?;
+            }
+
+            let name = player.name(i);
+            if i == self.current_player {
+                printer.selected_item(name, player.scoreboard.grand_total())?;
+            } else {
+                printer.item(name, player.scoreboard.grand_total())?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn setup_terminal<W: io::Write>(w: &mut W) -> Result<()> {
+    crossterm::queue!(w, terminal::Clear(terminal::ClearType::All), cursor::Hide)?;
+
+    Ok(())
+}
+
+fn cleanup_terminal<W: io::Write>(w: &mut W) -> Result<()> {
+    crossterm::queue!(w, cursor::Show)?;
+
+    Ok(())
+}
+
+/// Describes how scoring `score` in the upper section category of `face`
+/// compares with three of that face, which adds up to the bonus threshold.
+fn par_note(score: u32, face: u32) -> String {
+    let par = 3 * face;
+    if score > par {
+        format!("+{} over par", score - par)
+    } else if score < par {
+        format!("-{} under par", par - score)
+    } else {
+        "on par".to_string()
+    }
+}
+
+fn emote(key: char) -> Option<&'static str> {
+    EMOTES
+        .iter()
+        .find(|(emote_key, _)| *emote_key == key)
+        .map(|(_, text)| *text)
+}
+
+fn category_index(category: category::Category) -> usize {
+    category::CATEGORIES
+        .iter()
+        .position(|c| *c == category)
+        .unwrap()
+}
+
+fn parse_face(c: char) -> Option<Dice> {
+    if let Some(d) = c.to_digit(10) {
+        if 0 < d && d <= Dice::NUM_FACES as u32 {
+            return Some(Dice::from(d as u8 - 1));
+        }
+    }
+    None
+}
+
+fn parse_dice_number(c: char) -> Option<usize> {
+    if let Some(d) = c.to_digit(10) {
+        let d = d as usize;
+        if 0 < d && d <= DiceSet::NUM_DICE {
+            return Some(d - 1);
+        }
+    }
+    None
+}
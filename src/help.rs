@@ -0,0 +1,230 @@
+use crate::category::{self, Category};
+use crate::charset::Charset;
+use crate::scoreboard::{UPPER_SECTION_BONUS, UPPER_SECTION_BONUS_THRESHOLD};
+use crate::MAX_ROLLS;
+
+use anyhow::Result;
+use crossterm::event::KeyCode;
+use crossterm::style::{Color, ContentStyle, StyledContent};
+use crossterm::{cursor, style, terminal};
+use std::io;
+
+const KEY_BINDINGS: [(&str, &str); 20] = [
+    ("Up / K / W", "select the previous scoring category"),
+    ("Down / J / S", "select the next scoring category"),
+    ("Home", "select the first available category"),
+    ("End", "select the last available category"),
+    ("Enter / Space", "score the dice in the selected category"),
+    ("1 - 5", "mark or unmark a die to be re-rolled"),
+    ("R", "roll the marked dice"),
+    ("H", "suggest dice to re-roll or a category to score"),
+    ("1 - 6", "scorekeeper, sandbox: type the faces die by die"),
+    (
+        "Tab / Shift+Tab",
+        "scorekeeper, sandbox: select another die",
+    ),
+    (
+        "Left / Right",
+        "scorekeeper, sandbox: change the selected die",
+    ),
+    ("+ / -", "sandbox: change the selected die"),
+    ("[ / ]", "sandbox: change the roll number"),
+    ("Delete", "sandbox: clear the selected category"),
+    ("T", "networked games: type a chat message"),
+    ("N / O", "networked games: say \"nice roll!\" or \"ouch\""),
+    ("PgUp / PgDn", "networked games: scroll the chat"),
+    ("F1 / ?", "open or close this help"),
+    ("Esc / Q", "close this help, or quit the game"),
+    ("Ctrl+C", "quit the game"),
+];
+
+#[derive(Copy, Clone, PartialEq)]
+enum Page {
+    Keys,
+    Rules,
+}
+
+const PAGES: [Page; 2] = [Page::Keys, Page::Rules];
+
+/// Full-screen overlay listing key bindings and the rules of the game.
+pub struct HelpScreen {
+    page: usize,
+    scroll: usize,
+    charset: Charset,
+}
+
+impl HelpScreen {
+    pub fn new(charset: Charset) -> Self {
+        Self {
+            page: 0,
+            scroll: 0,
+            charset,
+        }
+    }
+
+    /// Handles a key on a terminal `height` rows high.
+    pub fn on_key(&mut self, code: KeyCode, height: u16) {
+        let page_height = page_height(height);
+
+        match code {
+            KeyCode::Left | KeyCode::Char('h') | KeyCode::BackTab => {
+                self.page = (self.page + PAGES.len() - 1) % PAGES.len();
+                self.scroll = 0;
+            }
+            KeyCode::Right | KeyCode::Char('l') | KeyCode::Tab => {
+                self.page = (self.page + 1) % PAGES.len();
+                self.scroll = 0;
+            }
+            KeyCode::Up | KeyCode::Char('k') | KeyCode::Char('w') => {
+                self.scroll = self.scroll.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') | KeyCode::Char('s') => self.scroll += 1,
+            KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(page_height),
+            KeyCode::PageDown => self.scroll += page_height,
+            KeyCode::Home => self.scroll = 0,
+            KeyCode::End => self.scroll = usize::MAX,
+            _ => (),
+        }
+
+        let max_scroll = self.lines().len().saturating_sub(page_height);
+        self.scroll = self.scroll.min(max_scroll);
+    }
+
+    pub fn draw<W: io::Write>(&self, w: &mut W, height: u16) -> Result<()> {
+        crossterm::queue!(
+            w,
+            terminal::Clear(terminal::ClearType::All),
+            cursor::MoveTo(0, 0)
+        )?;
+
+        for (i, page) in PAGES.iter().enumerate() {
+            let title = format!(" {} ", page.title());
+            let style = if i == self.page {
+                ContentStyle::new()
+                    .foreground(Color::Black)
+                    .background(Color::Yellow)
+            } else {
+                ContentStyle::new()
+            };
+            crossterm::queue!(
+                w,
+                style::PrintStyledContent(StyledContent::new(style, title)),
+                style::Print(" ")
+            )?;
+        }
+
+        let arrows = match self.charset {
+            Charset::Unicode => "←/→: switch page  ↑/↓: scroll  Esc: close",
+            Charset::Ascii => "Left/Right: switch page  Up/Down: scroll  Esc: close",
+        };
+        crossterm::queue!(w, style::Print("   "), style::Print(arrows))?;
+
+        for (line, y) in self
+            .lines()
+            .iter()
+            .skip(self.scroll)
+            .take(page_height(height))
+            .zip(2..)
+        {
+            crossterm::queue!(w, cursor::MoveTo(0, y), style::Print(line))?;
+        }
+
+        Ok(())
+    }
+
+    fn lines(&self) -> Vec<String> {
+        match PAGES[self.page] {
+            Page::Keys => key_lines(),
+            Page::Rules => rule_lines(self.charset),
+        }
+    }
+}
+
+impl Page {
+    fn title(self) -> &'static str {
+        match self {
+            Page::Keys => "Keys",
+            Page::Rules => "Rules",
+        }
+    }
+}
+
+fn page_height(height: u16) -> usize {
+    // title and margin
+    (height as usize).saturating_sub(2).max(1)
+}
+
+fn key_lines() -> Vec<String> {
+    KEY_BINDINGS
+        .iter()
+        .map(|(keys, description)| format!("  {:18}{}", keys, description))
+        .collect()
+}
+
+fn rule_lines(charset: Charset) -> Vec<String> {
+    let mut lines = vec![
+        format!(
+            "Each turn, roll five dice up to {} times. Between rolls, mark any dice",
+            MAX_ROLLS
+        ),
+        "to roll again. Then score the dice in one of the open categories.".to_string(),
+        format!(
+            "The game ends once all {} categories are filled. Scoring dice that",
+            category::CATEGORIES.len()
+        ),
+        "don't fit a category puts a 0 there.".to_string(),
+        String::new(),
+        "Upper Section".to_string(),
+    ];
+
+    for category in &category::UPPER_SECTION {
+        lines.push(describe(*category, charset));
+    }
+    lines.push(format!(
+        "  If the upper section adds up to {} or more, you get a bonus of {}.",
+        UPPER_SECTION_BONUS_THRESHOLD, UPPER_SECTION_BONUS
+    ));
+    lines.push(format!(
+        "  That is three of each face ({} in total).",
+        UPPER_SECTION_BONUS_THRESHOLD
+    ));
+
+    lines.push(String::new());
+    lines.push("Lower Section".to_string());
+    for category in &category::LOWER_SECTION {
+        lines.push(describe(*category, charset));
+    }
+
+    lines.extend(
+        [
+            "",
+            "Bonus 5 of a Kind",
+            "  Once 5 of a Kind is scored with 50, every further 5 of a Kind may be",
+            "  scored there again for another 50 points. There is no Joker rule:",
+            "  otherwise five of a kind only counts where it fits normally.",
+        ]
+        .iter()
+        .map(|line| line.to_string()),
+    );
+
+    lines
+}
+
+fn describe(category: Category, charset: Charset) -> String {
+    let description = match category {
+        Category::Ones => "sum of all 1s",
+        Category::Twos => "sum of all 2s",
+        Category::Threes => "sum of all 3s",
+        Category::Fours => "sum of all 4s",
+        Category::Fives => "sum of all 5s",
+        Category::Sixes => "sum of all 6s",
+        Category::ThreeOfAKind => "at least three dice the same: sum of all dice",
+        Category::FourOfAKind => "at least four dice the same: sum of all dice",
+        Category::FullHouse => "three of one face and two of another: 25",
+        Category::SmallStraight => "four faces in a row: 30",
+        Category::LargeStraight => "five faces in a row: 40",
+        Category::FiveOfAKind => "all five dice the same: 50",
+        Category::Chance => "anything: sum of all dice",
+    };
+    format!("  {:17}{}", category.label(charset), description)
+}
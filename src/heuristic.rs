@@ -0,0 +1,235 @@
+use crate::category::{self, Category};
+use crate::dice::DiceSet;
+use crate::odds::{self, Hand, Holding, Transitions};
+use crate::scoreboard::Scoreboard;
+use crate::strategy::{Decision, Strategy, Turn};
+use crate::MAX_ROLLS;
+
+use anyhow::Result;
+use rand::RngCore;
+use serde_json::{json, Value};
+use std::path::Path;
+
+/// Typical size of the weights counted in points.
+const POINTS_SCALE: f64 = 5.0;
+
+/// Tunable parts of how the heuristic bot values a category choice.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Weights {
+    /// Points counted per point an upper section score is above par (three
+    /// of the face), while the bonus can still be reached.
+    pub upper_bonus_pressure: f64,
+    /// Points counted on top of the score of a straight.
+    pub straight_chasing: f64,
+    /// Share of a category's average score counted as lost by filling it.
+    pub opportunity_cost: f64,
+    /// Points counted against scoring zero in each category, in the order
+    /// of `category::CATEGORIES`.
+    pub sacrifice: [f64; category::CATEGORIES.len()],
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Self {
+            upper_bonus_pressure: 1.0,
+            straight_chasing: 0.0,
+            opportunity_cost: 1.0,
+            sacrifice: [0.0; category::CATEGORIES.len()],
+        }
+    }
+}
+
+impl Weights {
+    /// Number of weights, as used by `to_vec` and `from_slice`.
+    pub const LEN: usize = 3 + category::CATEGORIES.len();
+
+    pub fn to_vec(&self) -> Vec<f64> {
+        let mut weights = vec![
+            self.upper_bonus_pressure,
+            self.straight_chasing,
+            self.opportunity_cost,
+        ];
+        weights.extend_from_slice(&self.sacrifice);
+        weights
+    }
+
+    pub fn from_slice(weights: &[f64]) -> Result<Self> {
+        if weights.len() != Self::LEN {
+            anyhow::bail!("Expected {} weights, got {}", Self::LEN, weights.len());
+        }
+
+        let mut sacrifice = [0.0; category::CATEGORIES.len()];
+        sacrifice.copy_from_slice(&weights[3..]);
+        Ok(Self {
+            upper_bonus_pressure: weights[0],
+            straight_chasing: weights[1],
+            opportunity_cost: weights[2],
+            sacrifice,
+        })
+    }
+
+    /// Typical size of each weight, in the order of `to_vec`: the factors
+    /// are around 1, and the points added or taken off a few points.
+    pub fn scales() -> Vec<f64> {
+        let mut scales = vec![1.0, POINTS_SCALE, 1.0];
+        scales.extend_from_slice(&[POINTS_SCALE; category::CATEGORIES.len()]);
+        scales
+    }
+
+    /// Reads a profile written by `save`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let profile = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+        let profile: Value = serde_json::from_str(&profile)?;
+        let number = |value: &Value| {
+            value
+                .as_f64()
+                .ok_or_else(|| anyhow::anyhow!("Invalid profile: {}", path.display()))
+        };
+
+        let mut weights = Self {
+            upper_bonus_pressure: number(&profile["upper_bonus_pressure"])?,
+            straight_chasing: number(&profile["straight_chasing"])?,
+            opportunity_cost: number(&profile["opportunity_cost"])?,
+            ..Self::default()
+        };
+        for (category, sacrifice) in category::CATEGORIES
+            .iter()
+            .zip(weights.sacrifice.iter_mut())
+        {
+            *sacrifice = number(&profile["sacrifice"][category.id()])?;
+        }
+        Ok(weights)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let sacrifice: serde_json::Map<_, _> = category::CATEGORIES
+            .iter()
+            .zip(self.sacrifice.iter())
+            .map(|(category, sacrifice)| (category.id().to_string(), json!(sacrifice)))
+            .collect();
+        let profile = json!({
+            "upper_bonus_pressure": self.upper_bonus_pressure,
+            "straight_chasing": self.straight_chasing,
+            "opportunity_cost": self.opportunity_cost,
+            "sacrifice": sacrifice,
+        });
+        std::fs::write(path, serde_json::to_string_pretty(&profile)? + "\n")?;
+        Ok(())
+    }
+}
+
+/// Bot that plays each turn for the best category by a hand-made valuation
+/// with tunable `Weights`, rather than looking at the rest of the game.
+pub struct HeuristicStrategy {
+    weights: Weights,
+    transitions: Transitions,
+    /// Average score of a turn played in favor of each category.
+    averages: Vec<f64>,
+}
+
+impl HeuristicStrategy {
+    pub fn new(weights: Weights) -> Self {
+        let transitions = Transitions::new();
+        let averages = category::CATEGORIES
+            .iter()
+            .map(|category| {
+                odds::mean(&odds::score_distribution_with(
+                    &transitions,
+                    *category,
+                    &[],
+                    MAX_ROLLS,
+                    Holding::Optimal,
+                ))
+            })
+            .collect();
+
+        Self {
+            weights,
+            transitions,
+            averages,
+        }
+    }
+
+    pub fn set_weights(&mut self, weights: Weights) {
+        self.weights = weights;
+    }
+
+    fn value(&self, scoreboard: &Scoreboard, category: Category, dice_set: &DiceSet) -> f64 {
+        let index = odds::category_index(category);
+        let score = dice_set.score(category);
+        let mut value = score as f64;
+
+        // a bonus five of a kind leaves the category open
+        if scoreboard.category_score(category).is_none() {
+            value -= self.weights.opportunity_cost * self.averages[index];
+        }
+        if score == 0 {
+            value -= self.weights.sacrifice[index];
+        }
+        if let Some(face) = category.face() {
+            if scoreboard.upper_section_bonus_is_achievable() {
+                value += self.weights.upper_bonus_pressure * (score as f64 - 3.0 * face as f64);
+            }
+        }
+        if score > 0 && (category == Category::SmallStraight || category == Category::LargeStraight)
+        {
+            value += self.weights.straight_chasing;
+        }
+        value
+    }
+
+    fn best_category(&self, scoreboard: &Scoreboard, dice_set: &DiceSet) -> (Category, f64) {
+        category::CATEGORIES
+            .iter()
+            .filter(|category| scoreboard.category_is_available(**category, dice_set))
+            .map(|category| (*category, self.value(scoreboard, *category, dice_set)))
+            .fold(
+                None,
+                |best: Option<(Category, f64)>, (category, value)| match best {
+                    Some((_, best_value)) if best_value >= value => best,
+                    _ => Some((category, value)),
+                },
+            )
+            .unwrap()
+    }
+}
+
+impl Strategy for HeuristicStrategy {
+    fn decide(&mut self, turn: &Turn, _rng: &mut dyn RngCore) -> Decision {
+        let hand = Hand::from_dice(&turn.dice_set.0);
+        if turn.roll < MAX_ROLLS {
+            let values: Vec<f64> = self
+                .transitions
+                .hands
+                .iter()
+                .map(|hand| self.best_category(turn.scoreboard, &hand.to_dice_set()).1)
+                .collect();
+            let kept = self
+                .transitions
+                .best_kept(hand, &values, MAX_ROLLS - turn.roll);
+            if kept != hand {
+                return Decision::Reroll(kept.rerolled_from(turn.dice_set));
+            }
+        }
+        Decision::Score(self.best_category(turn.scoreboard, turn.dice_set).0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn weights_round_trip_through_a_slice() {
+        let mut weights = Weights::default();
+        weights.sacrifice[3] = 2.5;
+        let slice = weights.to_vec();
+        assert_eq!(slice.len(), Weights::LEN);
+        assert_eq!(Weights::from_slice(&slice).unwrap(), weights);
+        assert!(Weights::from_slice(&slice[1..]).is_err());
+        assert!(Weights::from_slice(&[slice, vec![0.0]].concat()).is_err());
+        assert_eq!(Weights::scales().len(), Weights::LEN);
+    }
+}
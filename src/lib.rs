@@ -0,0 +1,32 @@
+pub mod category;
+pub mod charset;
+pub mod dice;
+pub mod env;
+pub mod external;
+#[cfg(not(target_arch = "wasm32"))]
+mod ffi;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod game;
+#[cfg(not(target_arch = "wasm32"))]
+mod help;
+pub mod heuristic;
+pub mod odds;
+#[cfg(feature = "python")]
+mod python;
+pub mod rollout;
+pub mod scoreboard;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod server;
+pub mod simulator;
+pub mod solver;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod ssh;
+pub mod state;
+pub mod strategy;
+#[cfg(not(target_arch = "wasm32"))]
+mod table_printer;
+pub mod tournament;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub const MAX_ROLLS: usize = 3;
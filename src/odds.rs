@@ -0,0 +1,546 @@
+use crate::category::{self, Category};
+use crate::dice::{Dice, DiceSet};
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// Dice regardless of their order, as the number of dice showing each face.
+#[derive(Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub(crate) struct Hand([u8; Dice::NUM_FACES]);
+
+impl Hand {
+    pub(crate) fn from_dice<'a, I>(dice: I) -> Self
+    where
+        I: IntoIterator<Item = &'a Dice>,
+    {
+        let mut hand = Self::default();
+        for dice in dice {
+            hand.0[dice.pips() as usize - 1] += 1;
+        }
+        hand
+    }
+
+    pub(crate) fn len(self) -> usize {
+        self.0.iter().map(|x| *x as usize).sum()
+    }
+
+    fn add(self, other: Hand) -> Hand {
+        let mut sum = self;
+        for (x, y) in sum.0.iter_mut().zip(other.0.iter()) {
+            *x += y;
+        }
+        sum
+    }
+
+    /// All hands that can be made by keeping some of the dice in this hand.
+    pub(crate) fn subhands(self) -> Vec<Hand> {
+        let mut subhands = vec![Hand::default()];
+        for (face, count) in self.0.iter().enumerate() {
+            subhands = subhands
+                .into_iter()
+                .flat_map(|subhand| {
+                    (0..=*count).map(move |n| {
+                        let mut subhand = subhand;
+                        subhand.0[face] = n;
+                        subhand
+                    })
+                })
+                .collect();
+        }
+        subhands
+    }
+
+    /// Which of `dice_set` to roll again so that only this hand stays.
+    pub(crate) fn rerolled_from(self, dice_set: &DiceSet) -> [bool; DiceSet::NUM_DICE] {
+        let mut left = self;
+        let mut rerolled = [false; DiceSet::NUM_DICE];
+        for (dice, rerolled) in dice_set.0.iter().zip(rerolled.iter_mut()) {
+            let count = &mut left.0[dice.pips() as usize - 1];
+            if *count > 0 {
+                *count -= 1;
+            } else {
+                *rerolled = true;
+            }
+        }
+        rerolled
+    }
+
+    pub(crate) fn to_dice_set(self) -> DiceSet {
+        let mut dice_set = DiceSet::default();
+        let faces = self
+            .0
+            .iter()
+            .enumerate()
+            .flat_map(|(face, count)| std::iter::repeat_n(face as u8, *count as usize));
+        for (dice, face) in dice_set.0.iter_mut().zip(faces) {
+            *dice = Dice::from(face);
+        }
+        dice_set
+    }
+}
+
+/// Every hand that rolling `n` dice can produce, with its probability.
+fn roll_outcomes(n: usize) -> Vec<(Hand, f64)> {
+    let mut outcomes = HashMap::new();
+    outcomes.insert(Hand::default(), 1.0);
+
+    for _ in 0..n {
+        let mut next = HashMap::new();
+        for (hand, probability) in outcomes {
+            for face in 0..Dice::NUM_FACES {
+                let mut hand: Hand = hand;
+                hand.0[face] += 1;
+                *next.entry(hand).or_insert(0.0) += probability / Dice::NUM_FACES as f64;
+            }
+        }
+        outcomes = next;
+    }
+
+    outcomes.into_iter().collect()
+}
+
+/// Every hand of all dice and every hand that can be kept from them, with
+/// the hands rolling the other dice can turn a kept hand into.
+pub(crate) struct Transitions {
+    pub(crate) hands: Vec<Hand>,
+    pub(crate) index: HashMap<Hand, usize>,
+    kept_hands: Vec<Hand>,
+    kept_index: HashMap<Hand, usize>,
+    /// Indices of the kept hands that can be made from each hand.
+    pub(crate) subhands: Vec<Vec<usize>>,
+    /// For each kept hand, indices of the resulting hands and their
+    /// probabilities.
+    pub(crate) outcomes: Vec<Vec<(usize, f64)>>,
+}
+
+impl Transitions {
+    pub(crate) fn new() -> Self {
+        let hands: Vec<Hand> = roll_outcomes(DiceSet::NUM_DICE)
+            .into_iter()
+            .map(|(hand, _)| hand)
+            .collect();
+        let index: HashMap<_, _> = hands.iter().enumerate().map(|(i, h)| (*h, i)).collect();
+
+        let mut kept_hands = Vec::new();
+        let mut kept_index = HashMap::new();
+        let subhands = hands
+            .iter()
+            .map(|hand| {
+                hand.subhands()
+                    .into_iter()
+                    .map(|kept| {
+                        *kept_index.entry(kept).or_insert_with(|| {
+                            kept_hands.push(kept);
+                            kept_hands.len() - 1
+                        })
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let rolls: Vec<_> = (0..=DiceSet::NUM_DICE).map(roll_outcomes).collect();
+        let outcomes = kept_hands
+            .iter()
+            .map(|kept| {
+                rolls[DiceSet::NUM_DICE - kept.len()]
+                    .iter()
+                    .map(|(rolled, p)| (index[&kept.add(*rolled)], *p))
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            hands,
+            index,
+            kept_hands,
+            kept_index,
+            subhands,
+            outcomes,
+        }
+    }
+
+    pub(crate) fn kept_outcomes(&self, kept: Hand) -> &[(usize, f64)] {
+        &self.outcomes[self.kept_index[&kept]]
+    }
+
+    pub(crate) fn kept_hand(&self, kept: usize) -> Hand {
+        self.kept_hands[kept]
+    }
+
+    /// Which dice of `hand` to keep for the highest expected value, given
+    /// the value of each hand once no rolls are left. Keeping every die
+    /// means stopping.
+    pub(crate) fn best_kept(&self, hand: Hand, values: &[f64], rolls_left: usize) -> Hand {
+        if rolls_left == 0 {
+            return hand;
+        }
+
+        // value of each hand with one roll fewer than `rolls_left`
+        let mut values = values.to_vec();
+        for _ in 1..rolls_left {
+            values = self.values_with_one_more_roll(&values);
+        }
+
+        let kept_values: Vec<f64> = self
+            .outcomes
+            .iter()
+            .map(|outcomes| expectation(outcomes, &values))
+            .collect();
+        let mut best = None;
+        for kept in &self.subhands[self.index[&hand]] {
+            let kept_hand = self.kept_hand(*kept);
+            let value = kept_values[*kept];
+            let better = match best {
+                None => true,
+                // prefer keeping every die when it's just as good
+                Some((best_hand, best_value)) => {
+                    value > best_value + EPSILON
+                        || (kept_hand == hand && best_hand != hand && value > best_value - EPSILON)
+                }
+            };
+            if better {
+                best = Some((kept_hand, value));
+            }
+        }
+        best.unwrap().0
+    }
+
+    fn values_with_one_more_roll(&self, values: &[f64]) -> Vec<f64> {
+        let kept_values: Vec<f64> = self
+            .outcomes
+            .iter()
+            .map(|outcomes| expectation(outcomes, values))
+            .collect();
+        self.subhands
+            .iter()
+            .map(|subhands| {
+                subhands
+                    .iter()
+                    .map(|kept| kept_values[*kept])
+                    .fold(f64::MIN, f64::max)
+            })
+            .collect()
+    }
+}
+
+/// Exact chances of scoring in each category by the end of a turn, found by
+/// enumerating every outcome of the remaining rolls.
+pub struct OddsTable {
+    transitions: Transitions,
+    /// `chances[category][rolls_left][hand]` is the chance of scoring in
+    /// `category` from `hand`, holding dice in the best way for it.
+    chances: Vec<Vec<Vec<f64>>>,
+}
+
+impl OddsTable {
+    pub fn new(max_rolls_left: usize) -> Self {
+        let transitions = Transitions::new();
+
+        let chances = category::CATEGORIES
+            .iter()
+            .map(|category| {
+                let scored: Vec<f64> = transitions
+                    .hands
+                    .iter()
+                    .map(|hand| {
+                        if hand.to_dice_set().score(*category) > 0 {
+                            1.0
+                        } else {
+                            0.0
+                        }
+                    })
+                    .collect();
+
+                let mut chances = vec![scored];
+                for _ in 0..max_rolls_left {
+                    let prev = chances.last().unwrap();
+                    let kept_chances: Vec<f64> = transitions
+                        .outcomes
+                        .iter()
+                        .map(|outcomes| expectation(outcomes, prev))
+                        .collect();
+                    let next = transitions
+                        .subhands
+                        .iter()
+                        .map(|subhands| {
+                            subhands
+                                .iter()
+                                .map(|kept| kept_chances[*kept])
+                                .fold(0.0, f64::max)
+                        })
+                        .collect();
+                    chances.push(next);
+                }
+                chances
+            })
+            .collect();
+
+        Self {
+            transitions,
+            chances,
+        }
+    }
+
+    /// Chance of scoring in `category` at the end of the turn if the dice
+    /// marked in `rerolled` are rolled now (when any are and `rolls_left`
+    /// allows it) and the rest of the turn is played in favor of `category`.
+    pub fn chance(
+        &self,
+        category: Category,
+        dice_set: &DiceSet,
+        rerolled: &[bool; DiceSet::NUM_DICE],
+        rolls_left: usize,
+    ) -> f64 {
+        let chances = &self.chances[category_index(category)];
+
+        if rolls_left == 0 || rerolled.iter().all(|x| !x) {
+            let rolls_left = rolls_left.min(chances.len() - 1);
+            let hand = Hand::from_dice(&dice_set.0);
+            return chances[rolls_left][self.transitions.index[&hand]];
+        }
+
+        let kept = Hand::from_dice(
+            dice_set
+                .0
+                .iter()
+                .zip(rerolled.iter())
+                .filter(|(_, rerolled)| !**rerolled)
+                .map(|(dice, _)| dice),
+        );
+        let next = &chances[(rolls_left - 1).min(chances.len() - 1)];
+        expectation(self.transitions.kept_outcomes(kept), next)
+    }
+}
+
+/// How dice are held after the first of the remaining rolls.
+#[derive(Copy, Clone, PartialEq)]
+pub enum Holding {
+    /// Hold exactly the initially kept dice and roll all the others on
+    /// every roll.
+    Fixed,
+    /// Hold whatever gives the best chance of scoring in the category,
+    /// preferring higher expected scores among equal chances.
+    Optimal,
+}
+
+/// Probability of each score in `category` at the end of a turn that starts
+/// with `kept` dice held and `rolls` rolls left. The result is indexed by
+/// score.
+pub fn score_distribution(
+    category: Category,
+    kept: &[Dice],
+    rolls: usize,
+    holding: Holding,
+) -> Vec<f64> {
+    score_distribution_with(&Transitions::new(), category, kept, rolls, holding)
+}
+
+pub(crate) fn score_distribution_with(
+    transitions: &Transitions,
+    category: Category,
+    kept: &[Dice],
+    rolls: usize,
+    holding: Holding,
+) -> Vec<f64> {
+    let scores: Vec<usize> = transitions
+        .hands
+        .iter()
+        .map(|hand| hand.to_dice_set().score(category) as usize)
+        .collect();
+    let max_score = *scores.iter().max().unwrap();
+
+    let point_mass = |score: usize| {
+        let mut distribution = vec![0.0; max_score + 1];
+        distribution[score] = 1.0;
+        distribution
+    };
+    let mix = |outcomes: &[(usize, f64)], distributions: &[Vec<f64>]| {
+        let mut mixed = vec![0.0; max_score + 1];
+        for (hand, p) in outcomes {
+            for (x, y) in mixed.iter_mut().zip(distributions[*hand].iter()) {
+                *x += p * y;
+            }
+        }
+        mixed
+    };
+
+    let kept = Hand::from_dice(kept);
+    if rolls == 0 {
+        return point_mass(scores[transitions.index[&kept]]);
+    }
+
+    // distributions[hand] at the end of the turn, with one roll fewer left
+    // each iteration
+    let mut distributions: Vec<Vec<f64>> = scores.iter().map(|s| point_mass(*s)).collect();
+    for _ in 1..rolls {
+        distributions = match holding {
+            Holding::Fixed => {
+                // whatever was rolled, the same dice are rolled again
+                let rerolled = mix(transitions.kept_outcomes(kept), &distributions);
+                vec![rerolled; scores.len()]
+            }
+            Holding::Optimal => {
+                let kept_distributions: Vec<_> = transitions
+                    .outcomes
+                    .iter()
+                    .map(|outcomes| mix(outcomes, &distributions))
+                    .collect();
+                transitions
+                    .subhands
+                    .iter()
+                    .map(|subhands| {
+                        subhands
+                            .iter()
+                            .map(|kept| &kept_distributions[*kept])
+                            .max_by(|a, b| compare_distributions(a, b))
+                            .unwrap()
+                            .clone()
+                    })
+                    .collect()
+            }
+        };
+    }
+
+    mix(transitions.kept_outcomes(kept), &distributions)
+}
+
+/// Orders score distributions by the chance of scoring, then by the
+/// expected score.
+fn compare_distributions(a: &[f64], b: &[f64]) -> Ordering {
+    const EPSILON: f64 = 1e-12;
+
+    let (a_chance, b_chance) = (1.0 - a[0], 1.0 - b[0]);
+    if (a_chance - b_chance).abs() > EPSILON {
+        a_chance.partial_cmp(&b_chance).unwrap()
+    } else {
+        mean(a).partial_cmp(&mean(b)).unwrap()
+    }
+}
+
+/// Expected value of a distribution indexed by value.
+pub fn mean(distribution: &[f64]) -> f64 {
+    distribution
+        .iter()
+        .enumerate()
+        .map(|(x, p)| x as f64 * p)
+        .sum()
+}
+
+/// Chance of scoring anything at all, from a distribution indexed by score.
+/// Rounding can leave the chance of scoring nothing a hair above 1, which
+/// would otherwise come out as a negative chance.
+pub fn scoring_chance(distribution: &[f64]) -> f64 {
+    (1.0 - distribution[0]).max(0.0)
+}
+
+const EPSILON: f64 = 1e-9;
+
+pub(crate) fn expectation(outcomes: &[(usize, f64)], values: &[f64]) -> f64 {
+    outcomes.iter().map(|(hand, p)| p * values[*hand]).sum()
+}
+
+pub(crate) fn category_index(category: Category) -> usize {
+    category::CATEGORIES
+        .iter()
+        .position(|c| *c == category)
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dice::dice_set;
+
+    const ROLLS_OF_FIVE: f64 = 7776.0;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn transitions_cover_every_hand() {
+        let transitions = Transitions::new();
+        // multisets of five dice with six faces
+        assert_eq!(transitions.hands.len(), 252);
+        for outcomes in &transitions.outcomes {
+            assert_close(outcomes.iter().map(|(_, p)| p).sum(), 1.0);
+        }
+    }
+
+    #[test]
+    fn single_roll_chances() {
+        let chance = |category| score_distribution(category, &[], 1, Holding::Fixed)[0];
+        assert_close(1.0 - chance(Category::FullHouse), 300.0 / ROLLS_OF_FIVE);
+        assert_close(
+            1.0 - chance(Category::SmallStraight),
+            1200.0 / ROLLS_OF_FIVE,
+        );
+        assert_close(1.0 - chance(Category::LargeStraight), 240.0 / ROLLS_OF_FIVE);
+        assert_close(1.0 - chance(Category::FiveOfAKind), 6.0 / ROLLS_OF_FIVE);
+    }
+
+    #[test]
+    fn impossible_targets_have_no_chance() {
+        let kept = [Dice::from(0), Dice::from(1), Dice::from(2)];
+        for category in [
+            Category::FourOfAKind,
+            Category::FullHouse,
+            Category::FiveOfAKind,
+        ] {
+            let distribution = score_distribution(category, &kept, 1, Holding::Fixed);
+            let chance = scoring_chance(&distribution);
+            assert_eq!(chance, 0.0);
+            assert_eq!(format!("{:.1}", chance * 100.0), "0.0");
+        }
+    }
+
+    #[test]
+    fn five_of_a_kind_in_a_turn() {
+        let distribution = score_distribution(Category::FiveOfAKind, &[], 3, Holding::Optimal);
+        assert_close(distribution[50], 2_783_176.0 / 60_466_176.0);
+    }
+
+    #[test]
+    fn fixed_holding_only_counts_the_last_roll() {
+        let sixes = [Dice::from(5); 2];
+        let distribution = score_distribution(Category::FiveOfAKind, &sixes, 2, Holding::Fixed);
+        assert_close(distribution[50], 1.0 / 216.0);
+    }
+
+    #[test]
+    fn optimal_chance_expectation() {
+        // keep 5s and 6s before the last roll, and 4s to 6s before that
+        let distribution = score_distribution(Category::Chance, &[], 3, Holding::Optimal);
+        assert_close(mean(&distribution), 70.0 / 3.0);
+    }
+
+    #[test]
+    fn odds_table_chances() {
+        let odds = OddsTable::new(2);
+        let none = [false; DiceSet::NUM_DICE];
+
+        let sixes = dice_set([6, 6, 6, 6, 6]);
+        assert_close(odds.chance(Category::FiveOfAKind, &sixes, &none, 2), 1.0);
+
+        let straight = dice_set([1, 2, 3, 4, 6]);
+        assert_close(
+            odds.chance(Category::SmallStraight, &straight, &none, 0),
+            1.0,
+        );
+        assert_close(
+            odds.chance(Category::LargeStraight, &straight, &none, 0),
+            0.0,
+        );
+        // rolling the 6 again needs a 5
+        let last = [false, false, false, false, true];
+        assert_close(
+            odds.chance(Category::LargeStraight, &straight, &last, 1),
+            1.0 / 6.0,
+        );
+    }
+}
@@ -0,0 +1,465 @@
+//! Python extension module, built with `maturin develop --features python`.
+//!
+//! Dice are given as pips from 1 to 6, categories by their ids such as
+//! `"FullHouse"`, and decisions in the same form as the external bot
+//! protocol: `{"hold": [...]}`, where `True` keeps a die, or
+//! `{"category": ...}`.
+
+use crate::category::{self, Category};
+use crate::dice::{Dice, DiceSet};
+use crate::env;
+use crate::scoreboard;
+use crate::simulator;
+use crate::solver::{self, Objective};
+use crate::strategy::{Decision, Engine, Strategy};
+use crate::MAX_ROLLS;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+fn value_error(e: anyhow::Error) -> PyErr {
+    PyValueError::new_err(e.to_string())
+}
+
+fn parse_category(category: &str) -> PyResult<Category> {
+    category.parse().map_err(value_error)
+}
+
+fn decision_to_dict(py: Python<'_>, decision: Decision) -> PyResult<Bound<'_, PyDict>> {
+    let dict = PyDict::new(py);
+    match decision {
+        Decision::Reroll(rerolled) => dict.set_item("hold", rerolled.map(|rerolled| !rerolled))?,
+        Decision::Score(category) => dict.set_item("category", category.id())?,
+    }
+    Ok(dict)
+}
+
+#[pyclass(name = "DiceSet", module = "yachtee")]
+#[derive(Clone)]
+struct PyDiceSet(DiceSet);
+
+#[pymethods]
+impl PyDiceSet {
+    #[new]
+    fn new(dice: Vec<u8>) -> PyResult<Self> {
+        let dice = dice
+            .iter()
+            .map(|pips| match pips {
+                1..=6 => Ok(Dice::from(pips - 1)),
+                _ => Err(PyValueError::new_err(format!(
+                    "Dice must be a number from 1 to 6: {}",
+                    pips
+                ))),
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+        DiceSet::from_slice(&dice).map(Self).map_err(value_error)
+    }
+
+    #[getter]
+    fn dice(&self) -> Vec<u32> {
+        self.0 .0.iter().map(|dice| dice.pips() as u32).collect()
+    }
+
+    fn score(&self, category: &str) -> PyResult<u32> {
+        Ok(self.0.score(parse_category(category)?))
+    }
+
+    /// Score of the dice in every category, by category id.
+    fn scores<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        for category in &category::CATEGORIES {
+            dict.set_item(category.id(), self.0.score(*category))?;
+        }
+        Ok(dict)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("DiceSet({:?})", self.dice())
+    }
+}
+
+#[pyclass(name = "Scoreboard", module = "yachtee")]
+#[derive(Clone)]
+struct PyScoreboard(scoreboard::Scoreboard);
+
+#[pymethods]
+impl PyScoreboard {
+    #[new]
+    fn new() -> Self {
+        Self(scoreboard::Scoreboard::new())
+    }
+
+    fn is_finished(&self) -> bool {
+        self.0.game_is_finished()
+    }
+
+    fn category_is_available(&self, category: &str, dice_set: PyDiceSet) -> PyResult<bool> {
+        Ok(self
+            .0
+            .category_is_available(parse_category(category)?, &dice_set.0))
+    }
+
+    /// Scores `dice_set` in `category`, with the same bonus rules as the game.
+    fn choose_category(&mut self, category: &str, dice_set: PyDiceSet) -> PyResult<()> {
+        let category = parse_category(category)?;
+        if !self.0.category_is_available(category, &dice_set.0) {
+            return Err(PyValueError::new_err(format!(
+                "Category is not available: {}",
+                category.id()
+            )));
+        }
+        self.0.choose_category(category, &dice_set.0);
+        Ok(())
+    }
+
+    fn category_score(&self, category: &str) -> PyResult<Option<u32>> {
+        Ok(self.0.category_score(parse_category(category)?))
+    }
+
+    /// Scores of the filled categories, by category id.
+    fn scores<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        for category in &category::CATEGORIES {
+            if let Some(score) = self.0.category_score(*category) {
+                dict.set_item(category.id(), score)?;
+            }
+        }
+        Ok(dict)
+    }
+
+    fn upper_total(&self) -> u32 {
+        self.0.upper_total()
+    }
+
+    fn upper_section_bonus(&self) -> u32 {
+        self.0.upper_section_bonus()
+    }
+
+    fn lower_total(&self) -> u32 {
+        self.0.lower_total()
+    }
+
+    fn grand_total(&self) -> u32 {
+        self.0.grand_total()
+    }
+
+    fn __repr__(&self) -> String {
+        let scores: Vec<_> = category::CATEGORIES
+            .iter()
+            .filter_map(|category| {
+                self.0
+                    .category_score(*category)
+                    .map(|score| format!("{}={}", category.id(), score))
+            })
+            .collect();
+        format!("Scoreboard({})", scores.join(", "))
+    }
+}
+
+/// Single-player game for training agents, with the actions and observations
+/// described in the `env` module.
+#[pyclass(name = "Env", module = "yachtee")]
+struct PyEnv(env::Env);
+
+#[pymethods]
+impl PyEnv {
+    #[new]
+    #[pyo3(signature = (seed=0))]
+    fn new(seed: u64) -> Self {
+        Self(env::Env::new(seed))
+    }
+
+    fn reset(&mut self, seed: u64) -> Vec<f32> {
+        self.0.reset(seed)
+    }
+
+    /// Returns the next observation, the reward and whether the game is over.
+    fn step(&mut self, action: usize) -> PyResult<(Vec<f32>, f32, bool)> {
+        self.0.step(action).map_err(value_error)
+    }
+
+    fn action_is_legal(&self, action: usize) -> bool {
+        self.0.action_is_legal(action)
+    }
+
+    fn legal_action_mask(&self) -> Vec<bool> {
+        self.0.legal_action_mask()
+    }
+
+    fn observation(&self) -> Vec<f32> {
+        self.0.observation()
+    }
+
+    fn is_done(&self) -> bool {
+        self.0.is_done()
+    }
+
+    #[getter]
+    fn dice_set(&self) -> PyDiceSet {
+        PyDiceSet(*self.0.dice_set())
+    }
+
+    #[getter]
+    fn roll(&self) -> usize {
+        self.0.roll()
+    }
+
+    #[getter]
+    fn scoreboard(&self) -> PyScoreboard {
+        PyScoreboard(self.0.scoreboard().clone())
+    }
+}
+
+/// Many `Env`s stepped together, with observations and masks flattened one
+/// environment after another.
+#[pyclass(name = "BatchEnv", module = "yachtee")]
+struct PyBatchEnv(env::BatchEnv);
+
+#[pymethods]
+impl PyBatchEnv {
+    #[new]
+    fn new(seeds: Vec<u64>) -> Self {
+        Self(env::BatchEnv::new(&seeds))
+    }
+
+    fn __len__(&self) -> usize {
+        self.0.len()
+    }
+
+    fn reset(&mut self, seeds: Vec<u64>) -> PyResult<Vec<f32>> {
+        self.0.reset(&seeds).map_err(value_error)
+    }
+
+    fn step(
+        &mut self,
+        py: Python<'_>,
+        actions: Vec<usize>,
+    ) -> PyResult<(Vec<f32>, Vec<f32>, Vec<bool>)> {
+        let batch = &mut self.0;
+        py.allow_threads(|| batch.step(&actions))
+            .map_err(value_error)
+    }
+
+    fn legal_action_masks(&self) -> Vec<bool> {
+        self.0.legal_action_masks()
+    }
+}
+
+/// Position evaluation and decisions of the solver behind the `optimal` bot.
+#[pyclass(name = "Solver", module = "yachtee")]
+struct PySolver(solver::Solver);
+
+#[pymethods]
+impl PySolver {
+    #[new]
+    fn new(py: Python<'_>) -> Self {
+        Self(py.allow_threads(solver::Solver::new))
+    }
+
+    /// Decides what to do at roll `roll` (from 1) of a turn, playing for
+    /// `objective`: `"expected"`, `"target:<score>"` or `"win"`.
+    #[pyo3(signature = (scoreboard, dice_set, roll, objective="expected", opponents=Vec::new()))]
+    fn decide<'py>(
+        &self,
+        py: Python<'py>,
+        scoreboard: PyScoreboard,
+        dice_set: PyDiceSet,
+        roll: usize,
+        objective: &str,
+        opponents: Vec<PyScoreboard>,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        if !(1..=MAX_ROLLS).contains(&roll) {
+            return Err(PyValueError::new_err(format!(
+                "Roll must be a number from 1 to {}: {}",
+                MAX_ROLLS, roll
+            )));
+        }
+        if scoreboard.0.game_is_finished() {
+            return Err(PyValueError::new_err("The game is already finished"));
+        }
+        let objective: Objective = objective.parse().map_err(value_error)?;
+        let opponents: Vec<_> = opponents.iter().map(|opponent| &opponent.0).collect();
+        let decision = py.allow_threads(|| {
+            self.0.decide(
+                &scoreboard.0,
+                &opponents,
+                &dice_set.0,
+                MAX_ROLLS - roll,
+                objective,
+            )
+        });
+        let decision = decision.ok_or_else(|| PyValueError::new_err("The dice can't be scored"))?;
+        decision_to_dict(py, decision)
+    }
+
+    /// Distribution of the final score of a player about to start a turn,
+    /// indexed by score.
+    fn final_score_distribution(&self, scoreboard: PyScoreboard) -> Vec<f64> {
+        self.0.final_score_distribution(&scoreboard.0)
+    }
+
+    /// Expected final score of a player about to start a turn, under the
+    /// solver's model of the rest of the game. The model gives each open
+    /// category a turn of its own, so it is well below what good play gets
+    /// from an empty scoreboard, but it ranks positions the same way.
+    fn expected_final_score(&self, scoreboard: PyScoreboard) -> f64 {
+        self.0
+            .final_score_distribution(&scoreboard.0)
+            .iter()
+            .enumerate()
+            .map(|(score, p)| score as f64 * p)
+            .sum()
+    }
+
+    /// Chance of each player finishing ahead of all the others, counting ties
+    /// as half wins.
+    fn win_chances(&self, scoreboards: Vec<PyScoreboard>) -> Vec<f64> {
+        let scoreboards: Vec<_> = scoreboards.iter().map(|scoreboard| &scoreboard.0).collect();
+        self.0.win_chances(&scoreboards)
+    }
+}
+
+/// Plays `games` games between `bots`, given in the same form as the game's
+/// `--bot`, and returns their final scores in each game. `seed` fixes the
+/// dice and the bots' chances, to play the same games again.
+#[pyfunction]
+#[pyo3(signature = (bots, games, seed=None))]
+fn simulate(
+    py: Python<'_>,
+    bots: Vec<String>,
+    games: usize,
+    seed: Option<u64>,
+) -> PyResult<Vec<Vec<u32>>> {
+    if bots.is_empty() {
+        return Err(PyValueError::new_err("At least one bot is needed"));
+    }
+    let engines = bots
+        .iter()
+        .map(|bot| bot.parse::<Engine>())
+        .collect::<anyhow::Result<Vec<_>>>()
+        .map_err(value_error)?;
+
+    py.allow_threads(|| {
+        let mut strategies: Vec<_> = engines.into_iter().map(Engine::strategy).collect();
+        let mut rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        (0..games)
+            .map(|_| {
+                let mut players: Vec<&mut dyn Strategy> =
+                    strategies.iter_mut().map(|s| s.as_mut() as _).collect();
+                simulator::play_game(&mut players, &mut rng)
+            })
+            .collect::<anyhow::Result<Vec<_>>>()
+    })
+    .map_err(value_error)
+}
+
+/// Turns an `Env` action into a decision.
+#[pyfunction]
+fn decode_action(py: Python<'_>, action: usize) -> PyResult<Bound<'_, PyDict>> {
+    let decision = env::decode_action(action)
+        .ok_or_else(|| PyValueError::new_err(format!("Unknown action: {}", action)))?;
+    decision_to_dict(py, decision)
+}
+
+#[pymodule]
+fn yachtee(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyDiceSet>()?;
+    m.add_class::<PyScoreboard>()?;
+    m.add_class::<PyEnv>()?;
+    m.add_class::<PyBatchEnv>()?;
+    m.add_class::<PySolver>()?;
+    m.add_function(wrap_pyfunction!(simulate, m)?)?;
+    m.add_function(wrap_pyfunction!(decode_action, m)?)?;
+
+    let categories: Vec<_> = category::CATEGORIES.iter().map(|c| c.id()).collect();
+    m.add("CATEGORIES", categories)?;
+    m.add("MAX_ROLLS", MAX_ROLLS)?;
+    m.add("NUM_HOLDS", env::NUM_HOLDS)?;
+    m.add("NUM_ACTIONS", env::NUM_ACTIONS)?;
+    m.add("OBSERVATION_LEN", env::OBSERVATION_LEN)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pyo3::ffi::c_str;
+    use std::ffi::CStr;
+
+    /// Runs `code` in an embedded interpreter, with the module built here as
+    /// `yachtee` and a `raises` helper checking for a `ValueError`.
+    fn run(code: &CStr) {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let module = PyModule::new(py, "yachtee").unwrap();
+            yachtee(&module).unwrap();
+            let globals = PyDict::new(py);
+            globals.set_item("yachtee", module).unwrap();
+            py.run(
+                c_str!(
+                    "def raises(f):\n    try:\n        f()\n    except ValueError:\n        \
+                     return True\n    return False\n"
+                ),
+                Some(&globals),
+                None,
+            )
+            .unwrap();
+            if let Err(e) = py.run(code, Some(&globals), None) {
+                panic!("{}", e);
+            }
+        });
+    }
+
+    #[test]
+    fn dice_and_scoreboards() {
+        run(c_str!(
+            r#"
+full_house = yachtee.DiceSet([2, 2, 3, 3, 3])
+assert full_house.dice == [2, 2, 3, 3, 3]
+assert full_house.score("FullHouse") == 25
+assert raises(lambda: yachtee.DiceSet([0, 1, 2, 3, 4]))
+assert raises(lambda: full_house.score("Sevens"))
+
+scoreboard = yachtee.Scoreboard()
+scoreboard.choose_category("FullHouse", full_house)
+assert scoreboard.scores() == {"FullHouse": 25}
+assert raises(lambda: scoreboard.choose_category("FullHouse", full_house))
+assert len(yachtee.CATEGORIES) == 13
+"#
+        ));
+    }
+
+    #[test]
+    fn envs_and_bots() {
+        run(c_str!(
+            r#"
+env = yachtee.Env(seed=1)
+assert len(env.observation()) == yachtee.OBSERVATION_LEN
+assert not env.legal_action_mask()[yachtee.NUM_HOLDS - 1]
+assert raises(lambda: env.step(yachtee.NUM_ACTIONS))
+_, reward, done = env.step(0)
+assert env.roll == 2 and reward == 0 and not done
+assert yachtee.decode_action(yachtee.NUM_HOLDS) == {"category": "Ones"}
+
+batch = yachtee.BatchEnv([1, 2])
+assert raises(lambda: batch.step([0, yachtee.NUM_HOLDS - 1]))
+assert len(batch.legal_action_masks()) == 2 * yachtee.NUM_ACTIONS
+
+solver = yachtee.Solver()
+decision = solver.decide(yachtee.Scoreboard(), yachtee.DiceSet([6, 6, 6, 6, 6]), 1)
+assert decision == {"category": "FiveOfAKind"}
+assert raises(lambda: solver.decide(yachtee.Scoreboard(), yachtee.DiceSet([1] * 5), 4))
+
+games = yachtee.simulate(["random", "greedy"], 3, seed=5)
+assert games == yachtee.simulate(["random", "greedy"], 3, seed=5)
+assert raises(lambda: yachtee.simulate([], 1))
+"#
+        ));
+    }
+}
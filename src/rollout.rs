@@ -0,0 +1,297 @@
+use crate::category::{self, Category};
+use crate::dice::DiceSet;
+use crate::odds::Hand;
+use crate::scoreboard::Scoreboard;
+use crate::simulator::Sum;
+use crate::strategy::{Decision, Strategy, Turn};
+use crate::MAX_ROLLS;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, RngCore, SeedableRng};
+use std::str::FromStr;
+use std::thread;
+
+/// How the simulated continuations are played.
+#[derive(Copy, Clone, PartialEq)]
+pub enum BasePolicy {
+    /// Keep the most common face and score the highest-scoring category.
+    Greedy,
+    /// Re-roll random dice and score a random category.
+    Random,
+}
+
+impl FromStr for BasePolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "greedy" => Ok(BasePolicy::Greedy),
+            "random" => Ok(BasePolicy::Random),
+            _ => Err(anyhow::anyhow!(
+                "Unknown base policy: {} (use `greedy` or `random`)",
+                s
+            )),
+        }
+    }
+}
+
+impl BasePolicy {
+    fn play<R: Rng + ?Sized>(
+        self,
+        scoreboard: &Scoreboard,
+        dice_set: &DiceSet,
+        rolls_left: usize,
+        rng: &mut R,
+    ) -> Decision {
+        let available: Vec<Category> = category::CATEGORIES
+            .iter()
+            .copied()
+            .filter(|category| scoreboard.category_is_available(*category, dice_set))
+            .collect();
+
+        match self {
+            BasePolicy::Greedy => {
+                // the first category wins ties, so zeros go to the upper section
+                let best = available
+                    .iter()
+                    .copied()
+                    .rev()
+                    .max_by_key(|category| dice_set.score(*category))
+                    .unwrap();
+                let made = GREEDY_PATTERNS.contains(&best) && dice_set.score(best) > 0;
+                if rolls_left == 0 || made {
+                    return Decision::Score(best);
+                }
+
+                let kept = dice_set
+                    .0
+                    .iter()
+                    .max_by_key(|dice| {
+                        let count = dice_set.0.iter().filter(|other| other == dice).count();
+                        (count, dice.pips())
+                    })
+                    .unwrap();
+                let mut rerolled = [false; DiceSet::NUM_DICE];
+                for (dice, rerolled) in dice_set.0.iter().zip(rerolled.iter_mut()) {
+                    *rerolled = dice != kept;
+                }
+                if rerolled == [false; DiceSet::NUM_DICE] {
+                    return Decision::Score(best);
+                }
+                Decision::Reroll(rerolled)
+            }
+            BasePolicy::Random => {
+                if rolls_left > 0 && rng.gen() {
+                    let rerolled = rng.gen();
+                    if rerolled != [false; DiceSet::NUM_DICE] {
+                        return Decision::Reroll(rerolled);
+                    }
+                }
+                Decision::Score(*available.choose(rng).unwrap())
+            }
+        }
+    }
+}
+
+impl Strategy for BasePolicy {
+    fn decide(&mut self, turn: &Turn, rng: &mut dyn RngCore) -> Decision {
+        self.play(turn.scoreboard, turn.dice_set, MAX_ROLLS - turn.roll, rng)
+    }
+}
+
+/// A greedy turn stops early once the dice make one of these.
+const GREEDY_PATTERNS: [Category; 4] = [
+    Category::FullHouse,
+    Category::SmallStraight,
+    Category::LargeStraight,
+    Category::FiveOfAKind,
+];
+
+/// Estimates the expected final score after each decision by playing the
+/// rest of the game many times with a base policy. It only goes through the
+/// scoreboard's rules, so it works for variants the solver doesn't model.
+#[derive(Copy, Clone, PartialEq)]
+pub struct Rollout {
+    pub policy: BasePolicy,
+    /// Continuations played for each decision.
+    pub simulations: usize,
+}
+
+impl FromStr for Rollout {
+    type Err = anyhow::Error;
+
+    /// Parses `rollout[:<simulations>[:<policy>]]`.
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let mut parts = s.splitn(3, ':');
+        if parts.next() != Some("rollout") {
+            anyhow::bail!("Unknown evaluator: {}", s);
+        }
+
+        let simulations = match parts.next() {
+            Some(simulations) => simulations.parse()?,
+            None => DEFAULT_SIMULATIONS,
+        };
+        if simulations < 2 {
+            anyhow::bail!("A rollout needs at least 2 simulations");
+        }
+        let policy = match parts.next() {
+            Some(policy) => policy.parse()?,
+            None => BasePolicy::Greedy,
+        };
+
+        Ok(Self {
+            policy,
+            simulations,
+        })
+    }
+}
+
+const DEFAULT_SIMULATIONS: usize = 500;
+
+/// Estimated expected final score after a decision.
+pub struct Estimate {
+    pub decision: Decision,
+    pub mean: f64,
+    /// Half the width of the 95% confidence interval around `mean`.
+    pub margin: f64,
+}
+
+impl Rollout {
+    /// Estimates every decision that can be made with `dice_set`, best first.
+    ///
+    /// Every decision is played out with the same dice, each continuation
+    /// rolling with a generator seeded from `rng`, so the estimates differ
+    /// by the decisions rather than by luck, and don't depend on how many
+    /// threads share the work.
+    pub fn evaluate<R: Rng + ?Sized>(
+        &self,
+        scoreboard: &Scoreboard,
+        dice_set: &DiceSet,
+        rolls_left: usize,
+        rng: &mut R,
+    ) -> Vec<Estimate> {
+        let decisions = decisions(scoreboard, dice_set, rolls_left);
+        let seed: u64 = rng.gen();
+
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        let sums = thread::scope(|scope| {
+            let handles: Vec<_> = (0..threads)
+                .map(|thread| {
+                    let decisions = &decisions;
+                    scope.spawn(move || {
+                        decisions
+                            .iter()
+                            .map(|decision| {
+                                let mut sum = Sum::default();
+                                for simulation in (thread..self.simulations).step_by(threads) {
+                                    let mut rng =
+                                        StdRng::seed_from_u64(seed.wrapping_add(simulation as u64));
+                                    let total = self.simulate(
+                                        scoreboard, dice_set, rolls_left, *decision, &mut rng,
+                                    );
+                                    sum.add(total as f64);
+                                }
+                                sum
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .fold(vec![Sum::default(); decisions.len()], |mut total, sums| {
+                    for (total, sum) in total.iter_mut().zip(sums) {
+                        total.merge(sum);
+                    }
+                    total
+                })
+        });
+
+        let mut estimates: Vec<Estimate> = decisions
+            .into_iter()
+            .zip(sums)
+            .map(|(decision, sum)| Estimate {
+                decision,
+                mean: sum.mean(),
+                margin: 1.96 * sum.standard_error(),
+            })
+            .collect();
+        estimates.sort_by(|a, b| b.mean.partial_cmp(&a.mean).unwrap());
+        estimates
+    }
+
+    pub fn decide<R: Rng + ?Sized>(
+        &self,
+        scoreboard: &Scoreboard,
+        dice_set: &DiceSet,
+        rolls_left: usize,
+        rng: &mut R,
+    ) -> Estimate {
+        self.evaluate(scoreboard, dice_set, rolls_left, rng)
+            .into_iter()
+            .next()
+            .unwrap()
+    }
+
+    /// Plays the rest of the game after `decision` and returns the final score.
+    fn simulate<R: Rng + ?Sized>(
+        &self,
+        scoreboard: &Scoreboard,
+        dice_set: &DiceSet,
+        rolls_left: usize,
+        decision: Decision,
+        rng: &mut R,
+    ) -> u32 {
+        let mut scoreboard = scoreboard.clone();
+        let mut dice_set = *dice_set;
+        let mut rolls_left = rolls_left;
+        let mut decision = decision;
+
+        loop {
+            match decision {
+                Decision::Reroll(rerolled) => {
+                    for (dice, rerolled) in dice_set.0.iter_mut().zip(rerolled.iter()) {
+                        if *rerolled {
+                            *dice = rng.gen();
+                        }
+                    }
+                    rolls_left -= 1;
+                }
+                Decision::Score(category) => {
+                    scoreboard.choose_category(category, &dice_set);
+                    if scoreboard.game_is_finished() {
+                        return scoreboard.grand_total();
+                    }
+                    dice_set = rng.gen();
+                    rolls_left = MAX_ROLLS - 1;
+                }
+            }
+            decision = self.policy.play(&scoreboard, &dice_set, rolls_left, rng);
+        }
+    }
+}
+
+impl Strategy for Rollout {
+    fn decide(&mut self, turn: &Turn, rng: &mut dyn RngCore) -> Decision {
+        let rolls_left = MAX_ROLLS - turn.roll;
+        Rollout::decide(self, turn.scoreboard, turn.dice_set, rolls_left, rng).decision
+    }
+}
+
+/// Every distinct decision: each way of keeping dice and each category.
+fn decisions(scoreboard: &Scoreboard, dice_set: &DiceSet, rolls_left: usize) -> Vec<Decision> {
+    let hand = Hand::from_dice(&dice_set.0);
+    let rerolls = hand
+        .subhands()
+        .into_iter()
+        .filter(|kept| rolls_left > 0 && *kept != hand)
+        .map(|kept| Decision::Reroll(kept.rerolled_from(dice_set)));
+    let scores = category::CATEGORIES
+        .iter()
+        .filter(|category| scoreboard.category_is_available(**category, dice_set))
+        .map(|category| Decision::Score(*category));
+    rerolls.chain(scores).collect()
+}
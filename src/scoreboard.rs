@@ -3,8 +3,18 @@ use crate::dice::DiceSet;
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 
+pub const UPPER_SECTION_BONUS_THRESHOLD: u32 = 63;
+pub const UPPER_SECTION_BONUS: u32 = 35;
+
+#[derive(Clone, PartialEq)]
 pub struct Scoreboard(HashMap<Category, u32>);
 
+impl Default for Scoreboard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Scoreboard {
     pub fn new() -> Self {
         Self(HashMap::new())
@@ -25,6 +35,14 @@ impl Scoreboard {
             .unwrap_or(true)
     }
 
+    /// Whether some dice would still score in `category`.
+    pub fn category_is_open(&self, category: Category) -> bool {
+        self.0
+            .get(&category)
+            .map(|score| category == Category::FiveOfAKind && *score > 0)
+            .unwrap_or(true)
+    }
+
     pub fn choose_category(&mut self, category: Category, dice_set: &DiceSet) {
         let score = dice_set.score(category);
 
@@ -39,13 +57,22 @@ impl Scoreboard {
         }
     }
 
+    pub fn clear_category(&mut self, category: Category) {
+        self.0.remove(&category);
+    }
+
+    /// Fills `category` with `score` as is, for restoring a saved scoreboard.
+    pub fn set_category_score(&mut self, category: Category, score: u32) {
+        self.0.insert(category, score);
+    }
+
     pub fn category_score(&self, category: Category) -> Option<u32> {
         self.0.get(&category).copied()
     }
 
     pub fn upper_section_bonus(&self) -> u32 {
-        if self.basic_total() > 62 {
-            35
+        if self.basic_total() >= UPPER_SECTION_BONUS_THRESHOLD {
+            UPPER_SECTION_BONUS
         } else {
             0
         }
@@ -66,10 +93,86 @@ impl Scoreboard {
         self.upper_total() + self.lower_total()
     }
 
-    fn basic_total(&self) -> u32 {
+    /// Sum of the upper section, excluding the bonus.
+    pub fn basic_total(&self) -> u32 {
         category::UPPER_SECTION
             .iter()
             .filter_map(|category| self.0.get(category))
             .sum()
     }
+
+    /// Points the upper section is ahead of (or behind) three of each face
+    /// among the categories scored so far.
+    pub fn upper_section_par_difference(&self) -> i32 {
+        category::UPPER_SECTION
+            .iter()
+            .filter_map(|category| {
+                let score = self.0.get(category)?;
+                Some(*score as i32 - 3 * category.face().unwrap() as i32)
+            })
+            .sum()
+    }
+
+    /// Whether the bonus can still be reached by scoring five of a kind in
+    /// every open upper section category.
+    pub fn upper_section_bonus_is_achievable(&self) -> bool {
+        let max_remaining: u32 = category::UPPER_SECTION
+            .iter()
+            .filter(|category| !self.0.contains_key(category))
+            .map(|category| 5 * category.face().unwrap())
+            .sum();
+        self.basic_total() + max_remaining >= UPPER_SECTION_BONUS_THRESHOLD
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dice::dice_set;
+
+    #[test]
+    fn bonus_five_of_a_kind() {
+        let fives = dice_set([5, 5, 5, 5, 5]);
+        let mut scoreboard = Scoreboard::new();
+        scoreboard.choose_category(Category::FiveOfAKind, &fives);
+        assert_eq!(scoreboard.category_score(Category::FiveOfAKind), Some(50));
+
+        // another five of a kind adds to the score
+        assert!(scoreboard.category_is_available(Category::FiveOfAKind, &fives));
+        scoreboard.choose_category(Category::FiveOfAKind, &fives);
+        assert_eq!(scoreboard.category_score(Category::FiveOfAKind), Some(100));
+        assert_eq!(scoreboard.grand_total(), 100);
+
+        // but other dice can't go there any more
+        let other = dice_set([1, 2, 3, 4, 5]);
+        assert!(!scoreboard.category_is_available(Category::FiveOfAKind, &other));
+        assert!(!scoreboard.game_is_finished());
+    }
+
+    #[test]
+    fn no_bonus_after_a_zero() {
+        let mut scoreboard = Scoreboard::new();
+        scoreboard.choose_category(Category::FiveOfAKind, &dice_set([1, 2, 3, 4, 5]));
+        assert_eq!(scoreboard.category_score(Category::FiveOfAKind), Some(0));
+        let fives = dice_set([5, 5, 5, 5, 5]);
+        assert!(!scoreboard.category_is_available(Category::FiveOfAKind, &fives));
+        assert!(!scoreboard.category_is_open(Category::FiveOfAKind));
+    }
+
+    #[test]
+    fn upper_section_bonus() {
+        let mut scoreboard = Scoreboard::new();
+        for face in 1..=6 {
+            let category = category::UPPER_SECTION[face as usize - 1];
+            // three of each face reach the threshold, with two more ones
+            scoreboard.choose_category(category, &dice_set([face, face, face, 1, 1]));
+        }
+        assert_eq!(scoreboard.basic_total(), 65);
+        assert_eq!(scoreboard.upper_section_bonus(), UPPER_SECTION_BONUS);
+
+        scoreboard.clear_category(Category::Sixes);
+        scoreboard.choose_category(Category::Sixes, &dice_set([6, 6, 1, 1, 1]));
+        assert_eq!(scoreboard.basic_total(), 59);
+        assert_eq!(scoreboard.upper_section_bonus(), 0);
+    }
 }
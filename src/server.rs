@@ -0,0 +1,1049 @@
+//! HTTP server with a JSON API to play games from other front-ends.
+//!
+//! | Method   | Path                         | Body                      |
+//! |----------|------------------------------|---------------------------|
+//! | `GET`    | `/games`                     |                           |
+//! | `POST`   | `/games`                     | `{"players": 2, "seed": 1}` (both optional) |
+//! | `GET`    | `/games/<id>`                |                           |
+//! | `DELETE` | `/games/<id>`                |                           |
+//! | `POST`   | `/games/<id>/holds/<dice>`   | (seat's token)            |
+//! | `POST`   | `/games/<id>/reroll`         | (seat's token)            |
+//! | `POST`   | `/games/<id>/score`          | `{"category": "FullHouse"}` (seat's token) |
+//! | `POST`   | `/games/<id>/seats`          | `{"name": "Ann"}`         |
+//! | `DELETE` | `/games/<id>/seats/<seat>`   | (seat's token)            |
+//! | `POST`   | `/games/<id>/chat`           | `{"text": "nice roll!"}` (seat's token) |
+//! | `GET`    | `/games/<id>/events?since=<seq>` |                       |
+//!
+//! A game seats up to 8 players. Every game endpoint answers with the game
+//! in the form of `game_json`, with the names in the seats and the sequence
+//! number of the latest event added. Dice are pips from 1 to 6 and are numbered from 0, categories are
+//! given by their ids, and errors come as `{"error": "..."}` with a 4xx
+//! status. Moves are checked against the rules here, so front-ends can't
+//! break them.
+//!
+//! Taking a seat also answers with the `seat` and a secret `token` for it.
+//! Moves, leaving the seat and chatting from it take that token in an
+//! `Authorization: Bearer <token>` header, so nobody can act for a player
+//! in another seat. Moves are only taken from the seat whose turn it is, so
+//! every seat has to be taken before the game can be played.
+//!
+//! Everything that happens in a game is recorded as an event, numbered from
+//! 1 by `seq`:
+//!
+//! - `{"type": "rolled", "player": 0, "roll": 1, "dice": [...]}`
+//! - `{"type": "held", "player": 0, "held": [...]}`
+//! - `{"type": "scored", "player": 0, "category": "Chance", "points": 21, "total": 21}`
+//! - `{"type": "turn", "player": 1}`
+//! - `{"type": "finished", "totals": [...]}`
+//! - `{"type": "joined", "seat": 0, "name": "Ann"}`
+//! - `{"type": "left", "seat": 0, "name": "Ann"}`
+//! - `{"type": "chat", "seat": 0, "name": "Ann", "text": "nice roll!"}`
+//!
+//! The events endpoint answers with those after `since` as an array, or,
+//! asked to upgrade to a WebSocket, pushes them as text messages as they
+//! happen. A client that reconnects with the last `seq` it saw gets the
+//! ones it missed first. Messages sent to the server are ignored.
+//!
+//! With a data directory, each game is also kept in `<id>.json` there and
+//! loaded again when the server starts.
+
+use crate::category::{self, Category};
+use crate::dice::DiceSet;
+use crate::scoreboard::Scoreboard;
+use crate::state::GameState;
+use crate::MAX_ROLLS;
+
+use anyhow::{bail, Result};
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tiny_http::{Header, Method, Request, Response};
+use tungstenite::protocol::{Message, Role, WebSocket};
+
+pub struct Server {
+    http: tiny_http::Server,
+    games: Mutex<Games>,
+    data_dir: Option<PathBuf>,
+    threads: usize,
+}
+
+pub struct ServerBuilder {
+    address: String,
+    data_dir: Option<PathBuf>,
+    threads: usize,
+}
+
+impl Default for ServerBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ServerBuilder {
+    pub fn new() -> Self {
+        Self {
+            address: "127.0.0.1:8080".to_string(),
+            data_dir: None,
+            threads: 4,
+        }
+    }
+
+    /// Starts listening, after loading the games saved in the data directory.
+    pub fn build(self) -> Result<Server> {
+        let mut games = Games::default();
+        if let Some(data_dir) = &self.data_dir {
+            fs::create_dir_all(data_dir)?;
+            games.load(data_dir)?;
+        }
+        let http = tiny_http::Server::http(&self.address)
+            .map_err(|e| anyhow::anyhow!("Failed to listen on {}: {}", self.address, e))?;
+
+        Ok(Server {
+            http,
+            games: Mutex::new(games),
+            data_dir: self.data_dir,
+            threads: self.threads,
+        })
+    }
+
+    /// Host and port to listen on.
+    pub fn address<S: Into<String>>(mut self, address: S) -> Self {
+        self.address = address.into();
+        self
+    }
+
+    /// Directory to keep the games in, so they outlive the server.
+    pub fn data_dir<P: Into<PathBuf>>(mut self, data_dir: Option<P>) -> Self {
+        self.data_dir = data_dir.map(Into::into);
+        self
+    }
+
+    /// Requests handled at the same time. WebSocket subscribers get a
+    /// thread of their own and don't count.
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+}
+
+#[derive(Default)]
+struct Games {
+    next_id: u64,
+    tables: BTreeMap<u64, Table>,
+    /// Channels of the WebSocket subscribers to each game.
+    subscribers: BTreeMap<u64, Vec<Sender<Value>>>,
+}
+
+impl Games {
+    fn load(&mut self, data_dir: &Path) -> Result<()> {
+        for entry in fs::read_dir(data_dir)? {
+            let path = entry?.path();
+            let id = match path.extension().and_then(|extension| extension.to_str()) {
+                Some("json") => path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str()?.parse().ok()),
+                _ => None,
+            };
+            if let Some(id) = id {
+                let table = fs::read_to_string(&path)
+                    .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+                let table = Table::from_json(&serde_json::from_str(&table)?)
+                    .map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))?;
+                self.tables.insert(id, table);
+                self.next_id = self.next_id.max(id + 1);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A game, the players seated at it and everything that has happened in it.
+#[derive(Clone)]
+struct Table {
+    game: GameState,
+    /// Name of the player in each seat, if someone has taken it.
+    seats: Vec<Option<String>>,
+    /// Token handed to the player in each seat, which they act for it with.
+    tokens: Vec<Option<String>>,
+    /// The sequence number of each event is its index plus one.
+    events: Vec<Value>,
+}
+
+impl Table {
+    fn new(game: GameState) -> Self {
+        let mut table = Self {
+            seats: vec![None; game.players()],
+            tokens: vec![None; game.players()],
+            game,
+            events: Vec::new(),
+        };
+        table.push(rolled_event(&table.game));
+        table
+    }
+
+    /// Sequence number of the latest event.
+    fn seq(&self) -> u64 {
+        self.events.len() as u64
+    }
+
+    fn push(&mut self, mut event: Value) {
+        event["seq"] = json!(self.seq() + 1);
+        self.events.push(event);
+    }
+
+    fn events_since(&self, since: u64) -> &[Value] {
+        let since = (since as usize).min(self.events.len());
+        &self.events[since..]
+    }
+
+    fn toggle_hold(&mut self, dice: usize) -> Result<()> {
+        self.game.toggle_hold(dice)?;
+        self.push(json!({
+            "type": "held",
+            "player": self.game.current_player(),
+            "held": self.game.held(),
+        }));
+        Ok(())
+    }
+
+    fn reroll(&mut self) -> Result<()> {
+        self.game.reroll()?;
+        self.push(rolled_event(&self.game));
+        Ok(())
+    }
+
+    fn choose_category(&mut self, category: Category) -> Result<()> {
+        let player = self.game.current_player();
+        let points = self.game.choose_category(category)?;
+        self.push(json!({
+            "type": "scored",
+            "player": player,
+            "category": category.id(),
+            "points": points,
+            "total": self.game.scoreboards()[player].grand_total(),
+        }));
+
+        if self.game.is_finished() {
+            let totals: Vec<_> = self
+                .game
+                .scoreboards()
+                .iter()
+                .map(Scoreboard::grand_total)
+                .collect();
+            self.push(json!({ "type": "finished", "totals": totals }));
+        } else {
+            self.push(json!({ "type": "turn", "player": self.game.current_player() }));
+            self.push(rolled_event(&self.game));
+        }
+        Ok(())
+    }
+
+    /// Seats a player in the first free seat, and returns the seat with
+    /// the token for it.
+    fn join(&mut self, name: &str) -> Result<(usize, String)> {
+        if name.trim().is_empty() {
+            bail!("Missing name");
+        }
+        if self.seats.iter().flatten().any(|seated| seated == name) {
+            bail!("{} is already seated", name);
+        }
+        let seat = match self.seats.iter().position(Option::is_none) {
+            Some(seat) => seat,
+            None => bail!("The table is full"),
+        };
+        let token = format!("{:032x}", rand::random::<u128>());
+        self.seats[seat] = Some(name.to_string());
+        self.tokens[seat] = Some(token.clone());
+        self.push(json!({ "type": "joined", "seat": seat, "name": name }));
+        Ok((seat, token))
+    }
+
+    /// The seat `token` was handed out for.
+    fn seat_of(&self, token: &str) -> std::result::Result<usize, ApiError> {
+        self.tokens
+            .iter()
+            .position(|seat_token| seat_token.as_deref() == Some(token))
+            .ok_or_else(|| ApiError::forbidden("Not seated at this table"))
+    }
+
+    /// Checks that `token` was handed out for the seat whose turn it is.
+    fn check_turn(&self, token: &str) -> std::result::Result<(), ApiError> {
+        if self.seat_of(token)? != self.game.current_player() {
+            return Err(ApiError::forbidden("It isn't your turn"));
+        }
+        Ok(())
+    }
+
+    fn leave(&mut self, seat: usize) -> Result<()> {
+        let name = match self.seats.get_mut(seat).and_then(Option::take) {
+            Some(name) => name,
+            None => bail!("Seat {} is empty", seat),
+        };
+        self.tokens[seat] = None;
+        self.push(json!({ "type": "left", "seat": seat, "name": name }));
+        Ok(())
+    }
+
+    /// Passes on a message from the player in `seat` to everyone following
+    /// the game.
+    fn chat(&mut self, seat: usize, text: &str) -> Result<()> {
+        let name = match self.seats.get(seat) {
+            Some(Some(name)) => name.clone(),
+            _ => bail!("Seat {} is empty", seat),
+        };
+        let text = text.trim();
+        if text.is_empty() {
+            bail!("Missing text");
+        }
+        if text.chars().count() > MAX_CHAT_LEN {
+            bail!("Messages are limited to {} characters", MAX_CHAT_LEN);
+        }
+        self.push(json!({ "type": "chat", "seat": seat, "name": name, "text": text }));
+        Ok(())
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "game": self.game.to_json(),
+            "seats": self.seats,
+            "tokens": self.tokens,
+            "events": self.events,
+        })
+    }
+
+    fn from_json(value: &Value) -> Result<Self> {
+        let game = GameState::from_json(&value["game"])?;
+        // names in the seats, and the tokens handed out for them
+        let names = |key: &str| match value[key].as_array() {
+            Some(names) if names.len() == game.players() => names
+                .iter()
+                .map(|name| match name {
+                    Value::Null => Ok(None),
+                    Value::String(name) => Ok(Some(name.clone())),
+                    _ => bail!("Invalid {}: {}", key, name),
+                })
+                .collect::<Result<Vec<_>>>(),
+            _ => bail!("Invalid {}", key),
+        };
+        let seats = names("seats")?;
+        let tokens = names("tokens")?;
+        if seats
+            .iter()
+            .zip(&tokens)
+            .any(|(name, token)| name.is_some() != token.is_some())
+        {
+            bail!("Invalid tokens");
+        }
+        let events = match value["events"].as_array() {
+            Some(events) => events.clone(),
+            None => bail!("Invalid events"),
+        };
+        Ok(Self {
+            game,
+            seats,
+            tokens,
+            events,
+        })
+    }
+}
+
+fn rolled_event(game: &GameState) -> Value {
+    json!({
+        "type": "rolled",
+        "player": game.current_player(),
+        "roll": game.roll(),
+        "dice": dice_set_json(game.dice_set()),
+    })
+}
+
+/// Error answered to a request, with its HTTP status.
+#[derive(Debug)]
+struct ApiError {
+    status: u16,
+    message: String,
+}
+
+impl ApiError {
+    fn not_found() -> Self {
+        Self {
+            status: 404,
+            message: "Not found".to_string(),
+        }
+    }
+
+    fn bad_request<S: ToString>(message: S) -> Self {
+        Self {
+            status: 400,
+            message: message.to_string(),
+        }
+    }
+
+    fn forbidden<S: ToString>(message: S) -> Self {
+        Self {
+            status: 403,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(e: anyhow::Error) -> Self {
+        Self::bad_request(e)
+    }
+}
+
+type ApiResult = std::result::Result<(u16, Value), ApiError>;
+
+/// Bodies larger than this are rejected.
+const MAX_BODY_LEN: u64 = 64 * 1024;
+
+/// Most players a game can be created for.
+const MAX_PLAYERS: usize = 8;
+
+/// Longest chat message, in characters.
+const MAX_CHAT_LEN: usize = 200;
+
+/// How often idle WebSocket subscribers are pinged, which is also how long
+/// it can take to notice they are gone.
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+
+impl Server {
+    pub fn address(&self) -> String {
+        self.http.server_addr().to_string()
+    }
+
+    /// Serves requests until the process is stopped.
+    pub fn run(&self) {
+        thread::scope(|scope| {
+            for _ in 0..self.threads {
+                scope.spawn(|| {
+                    for request in self.http.incoming_requests() {
+                        self.respond(request);
+                    }
+                });
+            }
+        });
+    }
+
+    fn respond(&self, mut request: Request) {
+        let result = match websocket_key(&request) {
+            Some(key) => match self.subscribe(&request) {
+                Ok((backlog, events)) => {
+                    upgrade(request, &key, backlog, events);
+                    return;
+                }
+                Err(e) => Err(e),
+            },
+            None => self.handle(&mut request),
+        };
+        let (status, body) = match result {
+            Ok((status, body)) => (status, body),
+            Err(e) => (e.status, json!({ "error": e.message })),
+        };
+
+        let body = if body.is_null() {
+            String::new()
+        } else {
+            body.to_string()
+        };
+        let mut response = Response::from_string(body).with_status_code(status);
+        // let front-ends served from elsewhere use the API from a browser
+        for header in &[
+            "Content-Type: application/json",
+            "Access-Control-Allow-Origin: *",
+            "Access-Control-Allow-Methods: GET, POST, DELETE, OPTIONS",
+            "Access-Control-Allow-Headers: Authorization, Content-Type",
+        ] {
+            response.add_header(header.parse::<Header>().unwrap());
+        }
+        // the client may be gone already, and nothing is left to do then
+        let _ = request.respond(response);
+    }
+
+    fn handle(&self, request: &mut Request) -> ApiResult {
+        let url = request.url().to_string();
+        let path = url.split('?').next().unwrap_or_default();
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        let method = request.method().clone();
+
+        match (&method, segments.as_slice()) {
+            (Method::Options, _) => Ok((204, Value::Null)),
+            (Method::Get, ["games"]) => Ok((200, self.list_games())),
+            (Method::Post, ["games"]) => self.create_game(&read_body(request)?),
+            (Method::Get, ["games", id]) => self.get_game(id),
+            (Method::Delete, ["games", id]) => self.delete_game(id),
+            (Method::Post, ["games", id, "holds", dice]) => {
+                let dice = dice
+                    .parse()
+                    .map_err(|_| ApiError::bad_request(format!("Invalid dice: {}", dice)))?;
+                let token = bearer_token(request)?;
+                self.with_table(id, |table| -> std::result::Result<_, ApiError> {
+                    table.check_turn(&token)?;
+                    Ok(table.toggle_hold(dice)?)
+                })
+            }
+            (Method::Post, ["games", id, "reroll"]) => {
+                let token = bearer_token(request)?;
+                self.with_table(id, |table| -> std::result::Result<_, ApiError> {
+                    table.check_turn(&token)?;
+                    Ok(table.reroll()?)
+                })
+            }
+            (Method::Post, ["games", id, "score"]) => {
+                let token = bearer_token(request)?;
+                let body = read_body(request)?;
+                let category = body["category"]
+                    .as_str()
+                    .ok_or_else(|| ApiError::bad_request("Missing category"))?
+                    .parse()?;
+                self.with_table(id, |table| -> std::result::Result<_, ApiError> {
+                    table.check_turn(&token)?;
+                    Ok(table.choose_category(category)?)
+                })
+            }
+            (Method::Post, ["games", id, "seats"]) => {
+                let body = read_body(request)?;
+                let name = body["name"]
+                    .as_str()
+                    .ok_or_else(|| ApiError::bad_request("Missing name"))?;
+                let mut joined = None;
+                let (status, mut body) =
+                    self.with_table(id, |table| table.join(name).map(|seat| joined = Some(seat)))?;
+                let (seat, token) = joined.unwrap();
+                body["seat"] = json!(seat);
+                body["token"] = json!(token);
+                Ok((status, body))
+            }
+            (Method::Delete, ["games", id, "seats", seat]) => {
+                let seat = seat
+                    .parse()
+                    .map_err(|_| ApiError::bad_request(format!("Invalid seat: {}", seat)))?;
+                let token = bearer_token(request)?;
+                self.with_table(id, |table| {
+                    if table.seat_of(&token)? != seat {
+                        return Err(ApiError::forbidden(format!("Seat {} isn't yours", seat)));
+                    }
+                    Ok(table.leave(seat)?)
+                })
+            }
+            (Method::Post, ["games", id, "chat"]) => {
+                let token = bearer_token(request)?;
+                let body = read_body(request)?;
+                let text = body["text"]
+                    .as_str()
+                    .ok_or_else(|| ApiError::bad_request("Missing text"))?;
+                // the sender is whoever the token was handed to
+                self.with_table(id, |table| -> std::result::Result<_, ApiError> {
+                    Ok(table.chat(table.seat_of(&token)?, text)?)
+                })
+            }
+            (Method::Get, ["games", id, "events"]) => {
+                let since = parse_since(&url)?;
+                let games = self.games.lock().unwrap();
+                let table = games
+                    .tables
+                    .get(&parse_id(id)?)
+                    .ok_or_else(ApiError::not_found)?;
+                Ok((200, json!(table.events_since(since))))
+            }
+            (_, ["games"]) | (_, ["games", ..]) => Err(ApiError {
+                status: 405,
+                message: format!("Method not allowed: {}", method),
+            }),
+            _ => Err(ApiError::not_found()),
+        }
+    }
+
+    fn list_games(&self) -> Value {
+        let games = self.games.lock().unwrap();
+        let games: Vec<_> = games
+            .tables
+            .iter()
+            .map(|(id, table)| {
+                json!({
+                    "id": id,
+                    "players": table.game.players(),
+                    "seats": table.seats,
+                    "finished": table.game.is_finished(),
+                })
+            })
+            .collect();
+        Value::Array(games)
+    }
+
+    fn create_game(&self, body: &Value) -> ApiResult {
+        let players = match &body["players"] {
+            Value::Null => 1,
+            players => match players.as_u64() {
+                Some(players) if (1..=MAX_PLAYERS as u64).contains(&players) => players as usize,
+                _ => {
+                    return Err(ApiError::bad_request(format!(
+                        "A game is for 1 to {} players",
+                        MAX_PLAYERS
+                    )))
+                }
+            },
+        };
+        let seed = match &body["seed"] {
+            Value::Null => rand::random(),
+            seed => seed
+                .as_u64()
+                .ok_or_else(|| ApiError::bad_request("Invalid seed"))?,
+        };
+        let table = Table::new(GameState::new(players, seed)?);
+
+        let mut games = self.games.lock().unwrap();
+        let id = games.next_id;
+        games.next_id += 1;
+        self.save(id, &table)?;
+        let body = table_json(id, &table);
+        games.tables.insert(id, table);
+        Ok((201, body))
+    }
+
+    fn delete_game(&self, id: &str) -> ApiResult {
+        let id = parse_id(id)?;
+        let mut games = self.games.lock().unwrap();
+        if !games.tables.contains_key(&id) {
+            return Err(ApiError::not_found());
+        }
+        // the file goes first, so a game that can't be deleted from disk
+        // isn't back after a restart
+        if let Some(data_dir) = &self.data_dir {
+            match fs::remove_file(game_path(data_dir, id)) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => {
+                    return Err(anyhow::Error::from(e).into())
+                }
+                _ => (),
+            }
+        }
+        games.tables.remove(&id);
+        // dropping the channels closes the subscribers' connections
+        games.subscribers.remove(&id);
+        Ok((200, json!({ "id": id })))
+    }
+
+    fn get_game(&self, id: &str) -> ApiResult {
+        let id = parse_id(id)?;
+        let games = self.games.lock().unwrap();
+        let table = games.tables.get(&id).ok_or_else(ApiError::not_found)?;
+        Ok((200, table_json(id, table)))
+    }
+
+    /// Makes a move with `f` and answers with the game, which is left as it
+    /// was if the move breaks the rules. The events `f` records are pushed
+    /// to the subscribers once the game is saved.
+    fn with_table<F, E>(&self, id: &str, f: F) -> ApiResult
+    where
+        F: FnOnce(&mut Table) -> std::result::Result<(), E>,
+        E: Into<ApiError>,
+    {
+        let id = parse_id(id)?;
+        let mut games = self.games.lock().unwrap();
+        let games = &mut *games;
+        let table = games.tables.get_mut(&id).ok_or_else(ApiError::not_found)?;
+
+        let mut changed = table.clone();
+        f(&mut changed).map_err(Into::into)?;
+        self.save(id, &changed)?;
+        let seq = table.seq();
+        *table = changed;
+
+        if let Some(subscribers) = games.subscribers.get_mut(&id) {
+            let events = table.events_since(seq);
+            subscribers.retain(|subscriber| {
+                events
+                    .iter()
+                    .all(|event| subscriber.send(event.clone()).is_ok())
+            });
+        }
+        Ok((200, table_json(id, table)))
+    }
+
+    /// Registers a WebSocket subscriber to a game, and returns the events it
+    /// missed along with the channel of the ones to come.
+    fn subscribe(
+        &self,
+        request: &Request,
+    ) -> std::result::Result<(Vec<Value>, Receiver<Value>), ApiError> {
+        let url = request.url();
+        let path = url.split('?').next().unwrap_or_default();
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        let id = match (request.method(), segments.as_slice()) {
+            (Method::Get, ["games", id, "events"]) => parse_id(id)?,
+            _ => return Err(ApiError::not_found()),
+        };
+        let since = parse_since(url)?;
+
+        // under the same lock, so no event can slip in between
+        let mut games = self.games.lock().unwrap();
+        let backlog = games
+            .tables
+            .get(&id)
+            .ok_or_else(ApiError::not_found)?
+            .events_since(since)
+            .to_vec();
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        games.subscribers.entry(id).or_default().push(sender);
+        Ok((backlog, receiver))
+    }
+
+    fn save(&self, id: u64, table: &Table) -> Result<()> {
+        if let Some(data_dir) = &self.data_dir {
+            // write to a temporary file first so a crash can't leave half a game
+            let path = game_path(data_dir, id);
+            let temporary = path.with_extension("json.tmp");
+            fs::write(&temporary, table.to_json().to_string())?;
+            fs::rename(&temporary, &path)?;
+        }
+        Ok(())
+    }
+}
+
+/// The token of a seat, sent as `Authorization: Bearer <token>`.
+fn bearer_token(request: &Request) -> std::result::Result<String, ApiError> {
+    request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv("Authorization"))
+        .and_then(|header| header.value.as_str().strip_prefix("Bearer "))
+        .map(|token| token.trim().to_string())
+        .ok_or(ApiError {
+            status: 401,
+            message: "Missing token".to_string(),
+        })
+}
+
+/// The key of a request to upgrade to a WebSocket.
+fn websocket_key(request: &Request) -> Option<String> {
+    let header = |name: &'static str| {
+        request
+            .headers()
+            .iter()
+            .find(|header| header.field.equiv(name))
+            .map(|header| header.value.as_str().to_string())
+    };
+    if !header("Upgrade")?.eq_ignore_ascii_case("websocket") {
+        return None;
+    }
+    header("Sec-WebSocket-Key")
+}
+
+fn upgrade(request: Request, key: &str, backlog: Vec<Value>, events: Receiver<Value>) {
+    let accept = tungstenite::handshake::derive_accept_key(key.as_bytes());
+    let response = Response::empty(101).with_header(
+        format!("Sec-WebSocket-Accept: {}", accept)
+            .parse::<Header>()
+            .unwrap(),
+    );
+    let stream = request.upgrade("websocket", response);
+    thread::spawn(move || push_events(stream, backlog, events));
+}
+
+/// Sends events to a subscriber until it goes away or the game is deleted.
+fn push_events<S: Read + Write>(stream: S, backlog: Vec<Value>, events: Receiver<Value>) {
+    let mut socket = WebSocket::from_raw_socket(stream, Role::Server, None);
+    for event in backlog {
+        if socket.send(Message::Text(event.to_string())).is_err() {
+            return;
+        }
+    }
+    loop {
+        let sent = match events.recv_timeout(PING_INTERVAL) {
+            Ok(event) => socket.send(Message::Text(event.to_string())),
+            Err(RecvTimeoutError::Timeout) => socket.send(Message::Ping(Vec::new())),
+            Err(RecvTimeoutError::Disconnected) => {
+                let _ = socket.close(None);
+                let _ = socket.flush();
+                return;
+            }
+        };
+        if sent.is_err() {
+            return;
+        }
+    }
+}
+
+fn game_path(data_dir: &Path, id: u64) -> PathBuf {
+    data_dir.join(format!("{}.json", id))
+}
+
+fn parse_id(id: &str) -> std::result::Result<u64, ApiError> {
+    id.parse().map_err(|_| ApiError::not_found())
+}
+
+/// The `since` parameter of the events endpoint, 0 by default.
+fn parse_since(url: &str) -> std::result::Result<u64, ApiError> {
+    let query = url.split_once('?').map_or("", |(_, query)| query);
+    match query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("since="))
+    {
+        Some(since) => since
+            .parse()
+            .map_err(|_| ApiError::bad_request(format!("Invalid since: {}", since))),
+        None => Ok(0),
+    }
+}
+
+fn read_body(request: &mut Request) -> std::result::Result<Value, ApiError> {
+    let mut body = String::new();
+    request
+        .as_reader()
+        .take(MAX_BODY_LEN + 1)
+        .read_to_string(&mut body)
+        .map_err(|e| ApiError::bad_request(format!("Failed to read the body: {}", e)))?;
+    if body.len() as u64 > MAX_BODY_LEN {
+        return Err(ApiError {
+            status: 413,
+            message: "Body too large".to_string(),
+        });
+    }
+    if body.trim().is_empty() {
+        return Ok(Value::Null);
+    }
+    serde_json::from_str(&body).map_err(|e| ApiError::bad_request(format!("Invalid JSON: {}", e)))
+}
+
+pub fn dice_set_json(dice_set: &DiceSet) -> Value {
+    let dice: Vec<_> = dice_set.0.iter().map(|dice| dice.pips()).collect();
+    json!(dice)
+}
+
+/// Filled categories by id, and the totals.
+pub fn scoreboard_json(scoreboard: &Scoreboard) -> Value {
+    let scores: serde_json::Map<_, _> = category::CATEGORIES
+        .iter()
+        .filter_map(|category| {
+            let score = scoreboard.category_score(*category)?;
+            Some((category.id().to_string(), json!(score)))
+        })
+        .collect();
+    json!({
+        "scores": scores,
+        "upper_subtotal": scoreboard.basic_total(),
+        "upper_section_bonus": scoreboard.upper_section_bonus(),
+        "upper_total": scoreboard.upper_total(),
+        "lower_total": scoreboard.lower_total(),
+        "grand_total": scoreboard.grand_total(),
+        "finished": scoreboard.game_is_finished(),
+    })
+}
+
+/// The game with the names in its seats and the latest sequence number.
+fn table_json(id: u64, table: &Table) -> Value {
+    let mut body = game_json(id, &table.game);
+    body["seats"] = json!(table.seats);
+    body["seq"] = json!(table.seq());
+    body
+}
+
+/// The whole game as front-ends see it. `categories` lists what the dice
+/// would score in each category for the current player.
+pub fn game_json(id: u64, game: &GameState) -> Value {
+    let categories: Vec<_> = category::CATEGORIES
+        .iter()
+        .map(|category| {
+            json!({
+                "category": category.id(),
+                "score": game.dice_set().score(*category),
+                "available": game.category_is_available(*category),
+            })
+        })
+        .collect();
+    let scoreboards: Vec<_> = game.scoreboards().iter().map(scoreboard_json).collect();
+
+    json!({
+        "id": id,
+        "players": game.players(),
+        "current_player": game.current_player(),
+        "roll": game.roll(),
+        "max_rolls": MAX_ROLLS,
+        "dice": dice_set_json(game.dice_set()),
+        "held": game.held(),
+        "can_reroll": game.can_reroll(),
+        "finished": game.is_finished(),
+        "categories": categories,
+        "scoreboards": scoreboards,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A server on a free port, keeping its games in a fresh directory.
+    fn server(name: &str) -> (Server, PathBuf) {
+        let data_dir =
+            std::env::temp_dir().join(format!("yachtee-server-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&data_dir);
+        let server = ServerBuilder::new()
+            .address("127.0.0.1:0")
+            .data_dir(Some(&data_dir))
+            .build()
+            .unwrap();
+        (server, data_dir)
+    }
+
+    #[test]
+    fn players_are_limited() {
+        let (server, data_dir) = server("players");
+        for players in [0, MAX_PLAYERS as u64 + 1, u64::MAX] {
+            let err = server
+                .create_game(&json!({ "players": players }))
+                .unwrap_err();
+            assert_eq!(err.status, 400);
+        }
+        let (status, _) = server
+            .create_game(&json!({ "players": MAX_PLAYERS }))
+            .unwrap();
+        assert_eq!(status, 201);
+        fs::remove_dir_all(data_dir).unwrap();
+    }
+
+    #[test]
+    fn games_that_fail_to_delete_are_kept() {
+        let (server, data_dir) = server("delete");
+        server.create_game(&json!({})).unwrap();
+
+        // a directory in the way of the file can't be removed like one
+        let path = game_path(&data_dir, 0);
+        fs::remove_file(&path).unwrap();
+        fs::create_dir(&path).unwrap();
+        assert!(server.delete_game("0").is_err());
+        assert!(server.get_game("0").is_ok());
+
+        fs::remove_dir(&path).unwrap();
+        assert!(server.delete_game("0").is_ok());
+        assert!(server.get_game("0").is_err());
+        fs::remove_dir_all(data_dir).unwrap();
+    }
+
+    /// Sends a request to `address` and returns the status and body.
+    fn request(address: &str, method: &str, path: &str, token: &str, body: Value) -> (u16, Value) {
+        let mut stream = std::net::TcpStream::connect(address).unwrap();
+        let body = body.to_string();
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: {}\r\nAuthorization: Bearer {}\r\n\
+             Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+            method,
+            path,
+            address,
+            token,
+            body.len(),
+            body
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let status = response[9..12].parse().unwrap();
+        let body = response.split("\r\n\r\n").nth(1).unwrap_or_default();
+        (status, serde_json::from_str(body).unwrap_or(Value::Null))
+    }
+
+    #[test]
+    fn chat_comes_from_the_token_holder() {
+        let (server, data_dir) = server("chat");
+        let server = Box::leak(Box::new(server));
+        let address = server.address();
+        thread::spawn(move || server.run());
+
+        request(&address, "POST", "/games", "", json!({ "players": 2 }));
+        let join = |name| {
+            request(
+                &address,
+                "POST",
+                "/games/0/seats",
+                "",
+                json!({ "name": name }),
+            )
+        };
+        let (_, ann) = join("Ann");
+        let (_, bob) = join("Bob");
+        let token = |joined: &Value| joined["token"].as_str().unwrap().to_string();
+        assert_eq!(bob["seat"], 1);
+
+        let chat = |token: &str, body| request(&address, "POST", "/games/0/chat", token, body);
+        // the seat in the body is no longer taken from the client
+        let (status, _) = chat(&token(&bob), json!({ "seat": 0, "text": "I'm Ann" }));
+        assert_eq!(status, 200);
+        let (_, events) = request(&address, "GET", "/games/0/events?since=3", "", Value::Null);
+        assert_eq!(events[0]["name"], "Bob");
+
+        let (status, _) = chat("guess", json!({ "text": "hi" }));
+        assert_eq!(status, 403);
+        let (status, _) = request(
+            &address,
+            "DELETE",
+            "/games/0/seats/0",
+            &token(&bob),
+            Value::Null,
+        );
+        assert_eq!(status, 403);
+        let (status, _) = request(
+            &address,
+            "DELETE",
+            "/games/0/seats/0",
+            &token(&ann),
+            Value::Null,
+        );
+        assert_eq!(status, 200);
+        let (status, _) = chat(&token(&ann), json!({ "text": "bye" }));
+        assert_eq!(status, 403);
+        fs::remove_dir_all(data_dir).unwrap();
+    }
+
+    #[test]
+    fn moves_come_from_the_seat_whose_turn_it_is() {
+        let (server, data_dir) = server("moves");
+        let server = Box::leak(Box::new(server));
+        let address = server.address();
+        thread::spawn(move || server.run());
+
+        request(&address, "POST", "/games", "", json!({ "players": 2 }));
+        let join = |name| {
+            let (_, joined) = request(
+                &address,
+                "POST",
+                "/games/0/seats",
+                "",
+                json!({ "name": name }),
+            );
+            joined["token"].as_str().unwrap().to_string()
+        };
+        let ann = join("Ann");
+        let bob = join("Bob");
+
+        let play = |path, token: &str, body| request(&address, "POST", path, token, body);
+        for path in ["/games/0/holds/0", "/games/0/reroll", "/games/0/score"] {
+            let (status, _) = play(path, &bob, json!({ "category": "Chance" }));
+            assert_eq!(status, 403);
+            let (status, _) = play(path, "guess", json!({ "category": "Chance" }));
+            assert_eq!(status, 403);
+        }
+        let (status, game) = play("/games/0/reroll", &ann, Value::Null);
+        assert_eq!(status, 200);
+        assert_eq!(game["roll"], 2);
+        let (status, _) = play("/games/0/score", &ann, json!({ "category": "Chance" }));
+        assert_eq!(status, 200);
+        let (status, _) = play("/games/0/reroll", &bob, Value::Null);
+        assert_eq!(status, 200);
+        fs::remove_dir_all(data_dir).unwrap();
+    }
+
+    #[test]
+    fn saved_tables_need_their_seats_and_events() {
+        let mut table = Table::new(GameState::new(2, 7).unwrap());
+        let (seat, token) = table.join("Ann").unwrap();
+        let restored = Table::from_json(&table.to_json()).unwrap();
+        assert_eq!(restored.seat_of(&token).unwrap(), seat);
+        assert!(Table::from_json(&table.game.to_json()).is_err());
+    }
+}
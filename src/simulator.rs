@@ -0,0 +1,185 @@
+use crate::dice::DiceSet;
+use crate::scoreboard::Scoreboard;
+use crate::strategy::{Decision, Strategy, Turn};
+use crate::MAX_ROLLS;
+
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+
+/// Plays a whole game between `strategies`, taking turns in order, and
+/// returns their final scores.
+pub fn play_game<R: Rng + ?Sized>(
+    strategies: &mut [&mut dyn Strategy],
+    rng: &mut R,
+) -> anyhow::Result<Vec<u32>> {
+    let mut rngs = strategies
+        .iter()
+        .map(|_| StdRng::from_rng(&mut *rng))
+        .collect::<Result<Vec<_>, _>>()?;
+    play_game_with_dice(strategies, &mut rngs)
+}
+
+/// Plays a game like `play_game`, but each player rolls with their own
+/// generator in `rngs`, so the dice one player gets don't depend on how the
+/// others play. Each strategy takes its chances from a generator of its own,
+/// seeded from its player's before the game starts, so they don't change the
+/// dice either.
+pub fn play_game_with_dice<R: Rng>(
+    strategies: &mut [&mut dyn Strategy],
+    rngs: &mut [R],
+) -> anyhow::Result<Vec<u32>> {
+    let mut scoreboards = vec![Scoreboard::new(); strategies.len()];
+    let mut strategy_rngs: Vec<_> = rngs
+        .iter_mut()
+        .map(|rng| StdRng::seed_from_u64(rng.gen()))
+        .collect();
+
+    // a bonus five of a kind takes a turn without filling a category, so
+    // players can finish at different times
+    while !scoreboards.iter().all(Scoreboard::game_is_finished) {
+        let players = strategies
+            .iter_mut()
+            .zip(rngs.iter_mut())
+            .zip(&mut strategy_rngs);
+        for (i, ((strategy, rng), strategy_rng)) in players.enumerate() {
+            if scoreboards[i].game_is_finished() {
+                continue;
+            }
+
+            let mut scoreboard = scoreboards[i].clone();
+            let opponents: Vec<_> = scoreboards
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .map(|(_, scoreboard)| scoreboard)
+                .collect();
+            play_turn(*strategy, &mut scoreboard, &opponents, rng, strategy_rng)?;
+            scoreboards[i] = scoreboard;
+        }
+    }
+
+    Ok(scoreboards.iter().map(Scoreboard::grand_total).collect())
+}
+
+/// Plays one turn of `strategy` onto `scoreboard`, rolling with `rng` and
+/// handing the strategy `strategy_rng`. A move the rules don't allow, such
+/// as a fourth roll, is an error.
+pub fn play_turn<R: Rng + ?Sized>(
+    strategy: &mut dyn Strategy,
+    scoreboard: &mut Scoreboard,
+    opponents: &[&Scoreboard],
+    rng: &mut R,
+    strategy_rng: &mut dyn RngCore,
+) -> anyhow::Result<()> {
+    let mut dice_set: DiceSet = rng.gen();
+    let mut roll = 1;
+
+    loop {
+        let turn = Turn {
+            dice_set: &dice_set,
+            roll,
+            scoreboard,
+            opponents,
+        };
+        let decision = strategy.decide(&turn, strategy_rng);
+        if !turn.allows(decision) {
+            match decision {
+                Decision::Reroll(_) if roll >= MAX_ROLLS => {
+                    anyhow::bail!("The dice can't be rolled more than {} times", MAX_ROLLS)
+                }
+                Decision::Reroll(_) => anyhow::bail!("A re-roll must roll at least one die"),
+                Decision::Score(category) => anyhow::bail!("{} is not available", category.id()),
+            }
+        }
+
+        match decision {
+            Decision::Reroll(rerolled) => {
+                for (dice, rerolled) in dice_set.0.iter_mut().zip(rerolled.iter()) {
+                    if *rerolled {
+                        *dice = rng.gen();
+                    }
+                }
+                roll += 1;
+            }
+            Decision::Score(category) => {
+                scoreboard.choose_category(category, &dice_set);
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Running sums for the mean and variance of samples.
+#[derive(Copy, Clone, Default)]
+pub struct Sum {
+    n: usize,
+    sum: f64,
+    sum_of_squares: f64,
+}
+
+impl Sum {
+    pub fn add(&mut self, x: f64) {
+        self.n += 1;
+        self.sum += x;
+        self.sum_of_squares += x * x;
+    }
+
+    pub fn merge(&mut self, other: Sum) {
+        self.n += other.n;
+        self.sum += other.sum;
+        self.sum_of_squares += other.sum_of_squares;
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.sum / self.n as f64
+    }
+
+    pub fn standard_error(&self) -> f64 {
+        let n = self.n as f64;
+        let variance = (self.sum_of_squares - self.sum * self.sum / n) / (n - 1.0);
+        (variance.max(0.0) / n).sqrt()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rollout::{BasePolicy, Rollout};
+
+    fn play_seeded(seed: u64) -> Vec<u32> {
+        let mut random = BasePolicy::Random;
+        let mut rollout = Rollout {
+            policy: BasePolicy::Random,
+            simulations: 2,
+        };
+        let mut players: [&mut dyn Strategy; 2] = [&mut random, &mut rollout];
+        play_game(&mut players, &mut StdRng::seed_from_u64(seed)).unwrap()
+    }
+
+    #[test]
+    fn seeded_games_play_out_the_same() {
+        assert_eq!(play_seeded(7), play_seeded(7));
+        assert_eq!(play_seeded(8), play_seeded(8));
+    }
+
+    /// Always makes the same move.
+    struct Stubborn(Decision);
+
+    impl Strategy for Stubborn {
+        fn decide(&mut self, _turn: &Turn, _rng: &mut dyn RngCore) -> Decision {
+            self.0
+        }
+    }
+
+    #[test]
+    fn illegal_moves_are_errors() {
+        for decision in [
+            Decision::Reroll([true; DiceSet::NUM_DICE]),
+            Decision::Reroll([false; DiceSet::NUM_DICE]),
+        ] {
+            let mut stubborn = Stubborn(decision);
+            let mut players: [&mut dyn Strategy; 1] = [&mut stubborn];
+            assert!(play_game(&mut players, &mut StdRng::seed_from_u64(7)).is_err());
+        }
+    }
+}
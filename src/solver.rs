@@ -0,0 +1,583 @@
+use crate::category::{self, Category};
+use crate::dice::DiceSet;
+use crate::odds::{self, Hand, Holding, Transitions};
+use crate::scoreboard::{Scoreboard, UPPER_SECTION_BONUS, UPPER_SECTION_BONUS_THRESHOLD};
+use crate::strategy::{Decision, Strategy, Turn};
+use crate::MAX_ROLLS;
+
+use rand::RngCore;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// What a player tries to maximize over the whole game.
+#[derive(Copy, Clone, PartialEq)]
+pub enum Objective {
+    /// The expected final score.
+    ExpectedScore,
+    /// The chance of finishing with at least this score.
+    Target(u32),
+    /// The chance of finishing ahead of every opponent.
+    Win,
+}
+
+/// Value of each final score under an `Objective`.
+struct Utility {
+    objective: Objective,
+    /// For each opponent, the chance of finishing below each score and the
+    /// chance of finishing with exactly that score.
+    opponents: Vec<(Vec<f64>, Vec<f64>)>,
+}
+
+impl Utility {
+    fn new(objective: Objective, opponents: Vec<Vec<f64>>) -> Self {
+        let opponents = opponents
+            .into_iter()
+            .map(|distribution| {
+                let below = distribution
+                    .iter()
+                    .scan(0.0, |sum, p| {
+                        let below = *sum;
+                        *sum += p;
+                        Some(below)
+                    })
+                    .collect();
+                (below, distribution)
+            })
+            .collect();
+
+        Self {
+            objective,
+            opponents,
+        }
+    }
+
+    fn at(&self, total: u32) -> f64 {
+        match self.objective {
+            Objective::Win if !self.opponents.is_empty() => {
+                let total = total as usize;
+                self.opponents
+                    .iter()
+                    .map(|(below, distribution)| match distribution.get(total) {
+                        // a tie counts as half a win
+                        Some(p) => below[total] + 0.5 * p,
+                        None => 1.0,
+                    })
+                    .product()
+            }
+            Objective::Target(target) => {
+                if total >= target {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            _ => total as f64,
+        }
+    }
+}
+
+impl FromStr for Objective {
+    type Err = anyhow::Error;
+
+    /// Parses `expected`, `target:<score>` or `win`.
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let mut parts = s.splitn(2, ':');
+        match (parts.next(), parts.next()) {
+            (Some("expected"), None) => Ok(Objective::ExpectedScore),
+            (Some("win"), None) => Ok(Objective::Win),
+            (Some("target"), Some(target)) => Ok(Objective::Target(target.parse()?)),
+            _ => Err(anyhow::anyhow!(
+                "Unknown objective: {} (use `expected`, `target:<score>` or `win`)",
+                s
+            )),
+        }
+    }
+}
+
+/// Plays a turn exactly for an `Objective`, valuing the rest of the game as
+/// if each open category will get a turn of its own played in its favor.
+pub struct Solver {
+    transitions: Transitions,
+    /// Distribution of the score of a turn played in favor of each category.
+    dedicated: Vec<Vec<f64>>,
+}
+
+impl Default for Solver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Solver {
+    pub fn new() -> Self {
+        let transitions = Transitions::new();
+        let dedicated = category::CATEGORIES
+            .iter()
+            .map(|category| {
+                odds::score_distribution_with(
+                    &transitions,
+                    *category,
+                    &[],
+                    MAX_ROLLS,
+                    Holding::Optimal,
+                )
+            })
+            .collect();
+
+        Self {
+            transitions,
+            dedicated,
+        }
+    }
+
+    /// Decides what to do with `dice_set`, considering the `opponents`'
+    /// scoreboards when playing to win. There is nothing to decide once no
+    /// category is left to score the dice in.
+    pub fn decide(
+        &self,
+        scoreboard: &Scoreboard,
+        opponents: &[&Scoreboard],
+        dice_set: &DiceSet,
+        rolls_left: usize,
+        objective: Objective,
+    ) -> Option<Decision> {
+        let opponents = match objective {
+            Objective::Win => opponents
+                .iter()
+                .map(|opponent| self.final_score_distribution(opponent))
+                .collect(),
+            _ => Vec::new(),
+        };
+        let utility = Utility::new(objective, opponents);
+        let choices = self.choice_values(scoreboard, &utility);
+        let hand = Hand::from_dice(&dice_set.0);
+
+        if rolls_left == 0 {
+            return Some(Decision::Score(best_choice(scoreboard, &choices, hand)?.0));
+        }
+
+        let values = self
+            .transitions
+            .hands
+            .iter()
+            .map(|hand| Some(best_choice(scoreboard, &choices, *hand)?.1))
+            .collect::<Option<Vec<f64>>>()?;
+        let kept = self.transitions.best_kept(hand, &values, rolls_left);
+        if kept == hand {
+            Some(Decision::Score(best_choice(scoreboard, &choices, hand)?.0))
+        } else {
+            Some(Decision::Reroll(kept.rerolled_from(dice_set)))
+        }
+    }
+
+    /// Distribution of the final score of a player about to start a turn
+    /// with `scoreboard`, indexed by score.
+    pub fn final_score_distribution(&self, scoreboard: &Scoreboard) -> Vec<f64> {
+        let (upper, lower): (Vec<Category>, Vec<Category>) = category::CATEGORIES
+            .iter()
+            .copied()
+            .filter(|category| scoreboard.category_score(*category).is_none())
+            .partition(|c| c.face().is_some());
+        let upper = self.total_distribution(&upper);
+        let lower = self.total_distribution(&lower);
+
+        let basic_total = scoreboard.basic_total();
+        let total = basic_total + scoreboard.lower_total();
+        let mut distribution =
+            vec![0.0; (total + UPPER_SECTION_BONUS) as usize + upper.len() + lower.len()];
+        for (u, p) in upper.iter().enumerate().filter(|(_, p)| **p > 0.0) {
+            let before = total + u as u32 + upper_section_bonus(basic_total + u as u32);
+            for (l, q) in lower.iter().enumerate() {
+                distribution[before as usize + l] += p * q;
+            }
+        }
+        distribution
+    }
+
+    /// Distribution of the final score of a player partway through a turn,
+    /// holding `dice_set` with `rolls_left` rolls to go, who plays the rest
+    /// of the turn for the highest expected score.
+    pub fn final_score_distribution_during_turn(
+        &self,
+        scoreboard: &Scoreboard,
+        dice_set: &DiceSet,
+        rolls_left: usize,
+    ) -> Vec<f64> {
+        if scoreboard.game_is_finished() {
+            return self.final_score_distribution(scoreboard);
+        }
+
+        // every hand can be scored somewhere while the game goes on
+        let utility = Utility::new(Objective::ExpectedScore, Vec::new());
+        let choices = self.choice_values(scoreboard, &utility);
+        let values: Vec<f64> = self
+            .transitions
+            .hands
+            .iter()
+            .map(|hand| best_choice(scoreboard, &choices, *hand).map_or(0.0, |(_, value)| value))
+            .collect();
+
+        // chance of holding each hand once the turn is over
+        let mut hands = vec![0.0; self.transitions.hands.len()];
+        hands[self.transitions.index[&Hand::from_dice(&dice_set.0)]] = 1.0;
+        for rolls_left in (1..=rolls_left).rev() {
+            let mut next = vec![0.0; hands.len()];
+            for (i, p) in hands.iter().enumerate().filter(|(_, p)| **p > 0.0) {
+                let hand = self.transitions.hands[i];
+                let kept = self.transitions.best_kept(hand, &values, rolls_left);
+                if kept == hand {
+                    next[i] += p;
+                } else {
+                    for (j, q) in self.transitions.kept_outcomes(kept) {
+                        next[*j] += p * q;
+                    }
+                }
+            }
+            hands = next;
+        }
+
+        let mut after_turn: HashMap<(Category, u32), Vec<f64>> = HashMap::new();
+        let mut distribution = Vec::new();
+        for (hand, p) in self.transitions.hands.iter().zip(&hands) {
+            if *p == 0.0 {
+                continue;
+            }
+            let dice_set = hand.to_dice_set();
+            let category = match best_choice(scoreboard, &choices, *hand) {
+                Some((category, _)) => category,
+                None => continue,
+            };
+            let after = after_turn
+                .entry((category, dice_set.score(category)))
+                .or_insert_with(|| {
+                    let mut scoreboard = scoreboard.clone();
+                    scoreboard.choose_category(category, &dice_set);
+                    self.final_score_distribution(&scoreboard)
+                });
+            if distribution.len() < after.len() {
+                distribution.resize(after.len(), 0.0);
+            }
+            for (total, q) in after.iter().enumerate() {
+                distribution[total] += p * q;
+            }
+        }
+        distribution
+    }
+
+    /// Estimated chance of each player finishing ahead of all the others,
+    /// counting ties as half wins.
+    pub fn win_chances(&self, scoreboards: &[&Scoreboard]) -> Vec<f64> {
+        let distributions: Vec<_> = scoreboards
+            .iter()
+            .map(|scoreboard| self.final_score_distribution(scoreboard))
+            .collect();
+        win_chances(&distributions)
+    }
+
+    /// Like `win_chances`, with the `current` player partway through a turn
+    /// holding `dice_set` with `rolls_left` rolls to go.
+    pub fn win_chances_during_turn(
+        &self,
+        scoreboards: &[&Scoreboard],
+        current: usize,
+        dice_set: &DiceSet,
+        rolls_left: usize,
+    ) -> Vec<f64> {
+        let distributions: Vec<_> = scoreboards
+            .iter()
+            .enumerate()
+            .map(|(i, scoreboard)| {
+                if i == current && !scoreboard.game_is_finished() {
+                    self.final_score_distribution_during_turn(scoreboard, dice_set, rolls_left)
+                } else {
+                    self.final_score_distribution(scoreboard)
+                }
+            })
+            .collect();
+        win_chances(&distributions)
+    }
+
+    /// For each category that can still be scored, the value of the game
+    /// after scoring each possible score there, indexed by score.
+    fn choice_values(&self, scoreboard: &Scoreboard, utility: &Utility) -> Vec<Option<Vec<f64>>> {
+        let unfilled: Vec<Category> = category::CATEGORIES
+            .iter()
+            .copied()
+            .filter(|category| scoreboard.category_score(*category).is_none())
+            .collect();
+
+        category::CATEGORIES
+            .iter()
+            .map(|category| {
+                if !scoreboard.category_is_open(*category) {
+                    return None;
+                }
+
+                let remaining = unfilled.iter().filter(|c| *c != category);
+                let (upper, lower): (Vec<Category>, Vec<Category>) =
+                    remaining.partition(|c| c.face().is_some());
+                let upper = self.total_distribution(&upper);
+                let lower = self.total_distribution(&lower);
+
+                let max_score = self.dedicated[odds::category_index(*category)].len() - 1;
+                let values = (0..=max_score)
+                    .map(|score| {
+                        value_after(scoreboard, *category, score as u32, &upper, &lower, utility)
+                    })
+                    .collect();
+                Some(values)
+            })
+            .collect()
+    }
+
+    /// Distribution of the sum of scores of `categories`, indexed by sum.
+    fn total_distribution(&self, categories: &[Category]) -> Vec<f64> {
+        categories.iter().fold(vec![1.0], |total, category| {
+            convolve(&total, &self.dedicated[odds::category_index(*category)])
+        })
+    }
+}
+
+/// Bot playing each turn with a `Solver`.
+pub struct SolverStrategy {
+    solver: Solver,
+    objective: Objective,
+}
+
+impl SolverStrategy {
+    pub fn new(objective: Objective) -> Self {
+        Self {
+            solver: Solver::new(),
+            objective,
+        }
+    }
+}
+
+impl Strategy for SolverStrategy {
+    fn decide(&mut self, turn: &Turn, _rng: &mut dyn RngCore) -> Decision {
+        self.solver
+            .decide(
+                turn.scoreboard,
+                turn.opponents,
+                turn.dice_set,
+                MAX_ROLLS - turn.roll,
+                self.objective,
+            )
+            .unwrap_or_else(|| Decision::Score(turn.first_available()))
+    }
+}
+
+/// Value of the game after scoring `score` in `category`, when the rest of
+/// the upper and lower sections add up to `upper` and `lower`.
+fn value_after(
+    scoreboard: &Scoreboard,
+    category: Category,
+    score: u32,
+    upper: &[f64],
+    lower: &[f64],
+    utility: &Utility,
+) -> f64 {
+    let basic_total = scoreboard.basic_total() + if category.face().is_some() { score } else { 0 };
+    let total = scoreboard.basic_total() + scoreboard.lower_total() + score;
+
+    // expected utility over the lower section for each total before it
+    let max_before = upper.len() as u32 + UPPER_SECTION_BONUS;
+    let after_lower: Vec<f64> = (0..max_before)
+        .map(|before| {
+            lower
+                .iter()
+                .enumerate()
+                .filter(|(_, p)| **p > 0.0)
+                .map(|(l, p)| p * utility.at(total + before + l as u32))
+                .sum()
+        })
+        .collect();
+
+    upper
+        .iter()
+        .enumerate()
+        .filter(|(_, p)| **p > 0.0)
+        .map(|(u, p)| {
+            let u = u as u32;
+            p * after_lower[(u + upper_section_bonus(basic_total + u)) as usize]
+        })
+        .sum()
+}
+
+/// Chance of each player finishing ahead of all the others given the
+/// distributions of their final scores, counting ties as half wins.
+fn win_chances(distributions: &[Vec<f64>]) -> Vec<f64> {
+    (0..distributions.len())
+        .map(|i| {
+            let opponents = distributions
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .map(|(_, distribution)| distribution.clone())
+                .collect();
+            let utility = Utility::new(Objective::Win, opponents);
+            distributions[i]
+                .iter()
+                .enumerate()
+                .map(|(total, p)| p * utility.at(total as u32))
+                .sum()
+        })
+        .collect()
+}
+
+fn upper_section_bonus(basic_total: u32) -> u32 {
+    if basic_total >= UPPER_SECTION_BONUS_THRESHOLD {
+        UPPER_SECTION_BONUS
+    } else {
+        0
+    }
+}
+
+/// The category to score `hand` in for the best value of the game, if any
+/// is left for it.
+fn best_choice(
+    scoreboard: &Scoreboard,
+    choices: &[Option<Vec<f64>>],
+    hand: Hand,
+) -> Option<(Category, f64)> {
+    let dice_set = hand.to_dice_set();
+    category::CATEGORIES
+        .iter()
+        .zip(choices.iter())
+        .filter_map(|(category, values)| {
+            let values = values.as_ref()?;
+            if !scoreboard.category_is_available(*category, &dice_set) {
+                return None;
+            }
+            Some((*category, values[dice_set.score(*category) as usize]))
+        })
+        .fold(
+            None,
+            |best: Option<(Category, f64)>, (category, value)| match best {
+                Some((_, best_value)) if best_value >= value => best,
+                _ => Some((category, value)),
+            },
+        )
+}
+
+fn convolve(a: &[f64], b: &[f64]) -> Vec<f64> {
+    let mut c = vec![0.0; a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate().filter(|(_, x)| **x > 0.0) {
+        for (j, y) in b.iter().enumerate() {
+            c[i + j] += x * y;
+        }
+    }
+    c
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dice::dice_set;
+
+    /// A scoreboard with only `open` left to score, every other category
+    /// holding a 0.
+    fn only_open(open: Category) -> Scoreboard {
+        let mut scoreboard = Scoreboard::new();
+        for category in category::CATEGORIES.iter().filter(|c| **c != open) {
+            scoreboard.set_category_score(*category, 0);
+        }
+        scoreboard
+    }
+
+    #[test]
+    fn scores_five_of_a_kind_at_once() {
+        let solver = Solver::new();
+        let decision = solver.decide(
+            &Scoreboard::new(),
+            &[],
+            &dice_set([6, 6, 6, 6, 6]),
+            2,
+            Objective::ExpectedScore,
+        );
+        assert_eq!(decision, Some(Decision::Score(Category::FiveOfAKind)));
+    }
+
+    #[test]
+    fn has_nothing_to_decide_once_the_scoreboard_is_full() {
+        let solver = Solver::new();
+        let mut scoreboard = only_open(Category::Chance);
+        scoreboard.set_category_score(Category::Chance, 20);
+        let dice = dice_set([2, 3, 3, 5, 6]);
+        for rolls_left in 0..MAX_ROLLS {
+            let decision = solver.decide(
+                &scoreboard,
+                &[],
+                &dice,
+                rolls_left,
+                Objective::ExpectedScore,
+            );
+            assert_eq!(decision, None);
+        }
+        let distribution = solver.final_score_distribution_during_turn(&scoreboard, &dice, 2);
+        assert_eq!(distribution[20], 1.0);
+    }
+
+    #[test]
+    fn plays_chance_optimally() {
+        let solver = Solver::new();
+        let scoreboard = only_open(Category::Chance);
+        let dice = dice_set([1, 4, 5, 6, 3]);
+
+        // with two rolls left, only 5s and 6s are worth keeping
+        let decision = solver.decide(&scoreboard, &[], &dice, 2, Objective::ExpectedScore);
+        assert_eq!(
+            decision,
+            Some(Decision::Reroll([true, true, false, false, true]))
+        );
+        // and with one, 4s as well
+        let decision = solver.decide(&scoreboard, &[], &dice, 1, Objective::ExpectedScore);
+        assert_eq!(
+            decision,
+            Some(Decision::Reroll([true, false, false, false, true]))
+        );
+    }
+
+    #[test]
+    fn final_score_of_the_last_turn() {
+        let solver = Solver::new();
+        let distribution = solver.final_score_distribution(&only_open(Category::Chance));
+        assert!((odds::mean(&distribution) - 70.0 / 3.0).abs() < 1e-9);
+
+        let mut finished = only_open(Category::Chance);
+        finished.choose_category(Category::Chance, &dice_set([6, 6, 6, 6, 6]));
+        let distribution = solver.final_score_distribution(&finished);
+        assert_eq!(distribution[30], 1.0);
+    }
+
+    #[test]
+    fn final_score_during_the_last_turn() {
+        let solver = Solver::new();
+        let scoreboard = only_open(Category::Chance);
+        let distribution =
+            solver.final_score_distribution_during_turn(&scoreboard, &dice_set([2, 3, 4, 5, 6]), 0);
+        assert_eq!(distribution[20], 1.0);
+
+        // rolling them all again leaves one roll to keep 4 or more on each die
+        let distribution =
+            solver.final_score_distribution_during_turn(&scoreboard, &dice_set([1, 1, 1, 1, 1]), 2);
+        assert!((distribution.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!((odds::mean(&distribution) - 85.0 / 4.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn win_chances_depend_on_the_dice() {
+        let solver = Solver::new();
+        let mut opponent = only_open(Category::Chance);
+        opponent.choose_category(Category::Chance, &dice_set([4, 4, 4, 4, 4]));
+        let current = only_open(Category::Chance);
+        let scoreboards = [&current, &opponent];
+
+        let chances = solver.win_chances_during_turn(&scoreboards, 0, &dice_set([6; 5]), 0);
+        assert_eq!(chances, [1.0, 0.0]);
+        let chances = solver.win_chances_during_turn(&scoreboards, 0, &dice_set([4; 5]), 0);
+        assert_eq!(chances, [0.5, 0.5]);
+        let chances = solver.win_chances_during_turn(&scoreboards, 0, &dice_set([1; 5]), 0);
+        assert_eq!(chances, [0.0, 1.0]);
+    }
+}
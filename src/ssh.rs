@@ -0,0 +1,1235 @@
+//! SSH server that plays the terminal game in each session, so nothing needs
+//! installing. `ssh -p 2222 <host>` opens the lobby, which lists the tables
+//! players wait at, each starting its game once all its seats are taken, and
+//! lets one open a table, join one or play alone. `ssh -t -p 2222 <host>
+//! table` skips the lobby and takes a seat at the first table with one free.
+//!
+//! The protocol is left to russh, running on a small tokio runtime, while
+//! each session is played on a thread of its own. Without a password, anyone
+//! who can reach the port may play, under the user name they log in with.
+//! Each connection may open one session channel, and what a client can make
+//! the server hold is bounded: the number of connections, the keys waiting
+//! to be read and the data received beyond the channel's window.
+
+mod lobby;
+
+use crate::charset::Charset;
+use crate::game::{Game, GameBuilder, Screen};
+use lobby::{Choice, Lobby, Rules, TableSummary};
+pub use lobby::{MAX_TABLE_PLAYERS, MIN_TABLE_PLAYERS};
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use crossbeam_channel::{select, Receiver, Sender};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::{cursor, style, terminal};
+use russh::keys::ssh_key::private::Ed25519Keypair;
+use russh::keys::ssh_key::LineEnding;
+use russh::keys::{Algorithm, HashAlg, PrivateKey};
+use russh::server::{Auth, Config, Handle, Msg, Session as Connected};
+use russh::{ChannelId, CryptoVec, MethodSet};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Write};
+use std::iter::Peekable;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::runtime::Runtime;
+
+/// Bytes a client may send before being given more room. Anything it sends
+/// past that is dropped.
+const WINDOW_SIZE: u32 = 1024 * 1024;
+/// Largest chunk of terminal output sent at once.
+const MAX_DATA_LEN: usize = 32 * 1024;
+/// Key events waiting for a session to read them, past which more keys are
+/// dropped.
+const MAX_QUEUED_KEYS: usize = 64;
+/// Environment variables kept per session, of which only the locale is used.
+const MAX_ENV_VARS: usize = 16;
+/// Longest user name, in characters, that players are shown under.
+const MAX_USER_LEN: usize = 32;
+/// Smallest terminal drawn on, in columns and rows; clients asking for less,
+/// or for nothing at all, get this much.
+const MIN_TERMINAL_SIZE: (u16, u16) = (20, 10);
+/// Largest number of columns or rows drawn on.
+const MAX_TERMINAL_SIZE: u16 = 1000;
+/// How often clients that have gone quiet are checked for, so their
+/// connections don't linger.
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(30);
+/// How often the lobby and the seat-waiting screen check for tables and
+/// players coming and going.
+const REFRESH_INTERVAL: Duration = Duration::from_millis(250);
+
+pub struct SshServer {
+    runtime: Runtime,
+    listener: TcpListener,
+    host_key: PrivateKey,
+    password: Option<String>,
+    max_connections: usize,
+    charset: Charset,
+    animation: bool,
+    table_players: usize,
+    tables: Arc<Mutex<Tables>>,
+}
+
+pub struct SshServerBuilder {
+    address: String,
+    host_key: PathBuf,
+    password: Option<String>,
+    max_connections: usize,
+    charset: Charset,
+    animation: bool,
+    table_players: usize,
+}
+
+impl Default for SshServerBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SshServerBuilder {
+    pub fn new() -> Self {
+        Self {
+            address: "127.0.0.1:2222".to_string(),
+            host_key: PathBuf::from("ssh_host_ed25519_key"),
+            password: None,
+            max_connections: 64,
+            charset: Charset::Unicode,
+            animation: true,
+            table_players: 2,
+        }
+    }
+
+    /// Starts listening, after loading the host key or generating it if
+    /// the file doesn't exist yet.
+    pub fn build(self) -> Result<SshServer> {
+        let host_key = load_host_key(&self.host_key)?;
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .enable_io()
+            .enable_time()
+            .build()?;
+        let listener = runtime
+            .block_on(TcpListener::bind(&self.address))
+            .map_err(|e| anyhow::anyhow!("Failed to listen on {}: {}", self.address, e))?;
+
+        Ok(SshServer {
+            runtime,
+            listener,
+            host_key,
+            password: self.password,
+            max_connections: self.max_connections,
+            charset: self.charset,
+            animation: self.animation,
+            table_players: self.table_players,
+            tables: Arc::new(Mutex::new(Tables::default())),
+        })
+    }
+
+    /// Host and port to listen on.
+    pub fn address<S: Into<String>>(mut self, address: S) -> Self {
+        self.address = address.into();
+        self
+    }
+
+    /// OpenSSH private key file of the server's ed25519 host key.
+    pub fn host_key<P: Into<PathBuf>>(mut self, host_key: P) -> Self {
+        self.host_key = host_key.into();
+        self
+    }
+
+    /// Password every user has to log in with. Without one, logging in
+    /// takes no authentication at all.
+    pub fn password(mut self, password: Option<String>) -> Self {
+        self.password = password;
+        self
+    }
+
+    /// Number of clients connected at once, past which new connections are
+    /// closed right away.
+    pub fn max_connections(mut self, connections: usize) -> Self {
+        self.max_connections = connections.max(1);
+        self
+    }
+
+    /// Characters to draw with when a client doesn't send its locale.
+    pub fn charset(mut self, charset: Charset) -> Self {
+        self.charset = charset;
+        self
+    }
+
+    pub fn animation(mut self, animation: bool) -> Self {
+        self.animation = animation;
+        self
+    }
+
+    /// Number of players tables wait for unless opened with another number,
+    /// within what the lobby lets players open tables for.
+    pub fn table_players(mut self, players: usize) -> Self {
+        self.table_players = players.clamp(MIN_TABLE_PLAYERS, MAX_TABLE_PLAYERS);
+        self
+    }
+}
+
+fn load_host_key(path: &Path) -> Result<PrivateKey> {
+    if !path.exists() {
+        let host_key = PrivateKey::from(Ed25519Keypair::from_seed(&rand::random()));
+        host_key
+            .write_openssh_file(path, LineEnding::LF)
+            .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", path.display(), e))?;
+        return Ok(host_key);
+    }
+
+    let host_key = PrivateKey::read_openssh_file(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+    if host_key.is_encrypted() {
+        bail!("{} is protected by a passphrase", path.display());
+    }
+    if host_key.algorithm() != Algorithm::Ed25519 {
+        bail!("{} isn't an ed25519 key", path.display());
+    }
+    Ok(host_key)
+}
+
+impl SshServer {
+    pub fn address(&self) -> String {
+        self.listener
+            .local_addr()
+            .map_or_else(|_| String::new(), |address| address.to_string())
+    }
+
+    /// Fingerprint of the host key, which clients show when they connect
+    /// for the first time.
+    pub fn fingerprint(&self) -> String {
+        self.host_key.fingerprint(HashAlg::Sha256).to_string()
+    }
+
+    /// Serves connections until the process is stopped.
+    pub fn run(&self) {
+        let (sessions, started) = crossbeam_channel::unbounded();
+        thread::scope(|scope| {
+            scope.spawn(|| self.runtime.block_on(self.accept(sessions)));
+            for (session, command) in started {
+                // a client that goes away only ends its own session
+                scope.spawn(move || self.run_session(session, &command));
+            }
+        });
+    }
+
+    /// Accepts connections, as many at once as allowed, and passes the
+    /// sessions they start on to `sessions`.
+    async fn accept(&self, sessions: Sender<(Session, String)>) {
+        let config = Arc::new(Config {
+            methods: if self.password.is_some() {
+                MethodSet::PASSWORD
+            } else {
+                MethodSet::NONE
+            },
+            keys: vec![self.host_key.clone()],
+            window_size: WINDOW_SIZE,
+            maximum_packet_size: MAX_DATA_LEN as u32,
+            // players may sit at a table for a long time without typing
+            inactivity_timeout: None,
+            keepalive_interval: Some(KEEPALIVE_INTERVAL),
+            auth_rejection_time_initial: Some(Duration::ZERO),
+            ..Config::default()
+        });
+        let connections = Arc::new(AtomicUsize::new(0));
+
+        loop {
+            let stream = match self.listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(_) => continue,
+            };
+            // only this loop adds connections, so none can slip in between
+            if connections.load(Ordering::SeqCst) >= self.max_connections {
+                continue;
+            }
+            let connection = Connection {
+                password: self.password.clone(),
+                charset: self.charset,
+                sessions: sessions.clone(),
+                runtime: tokio::runtime::Handle::current(),
+                user: String::new(),
+                opened: false,
+                channel: None,
+                _slot: Slot::take(&connections),
+            };
+            let config = config.clone();
+            tokio::spawn(async move {
+                if let Ok(session) = russh::server::run_stream(config, stream, connection).await {
+                    let _ = session.await;
+                }
+            });
+        }
+    }
+
+    fn run_session(&self, mut session: Session, command: &str) {
+        let output = session.output.clone();
+        let status = match command.trim() {
+            _ if session.size.is_none() => {
+                let _ = output.print(
+                    "A terminal is needed to play; connect with ssh -t, or without a command.\r\n",
+                );
+                1
+            }
+            "" => {
+                self.visit_lobby(session);
+                0
+            }
+            "table" => {
+                if self.sit_at_free_table(&mut session) {
+                    self.visit_lobby(session);
+                }
+                0
+            }
+            command => {
+                let _ = output.print(&format!(
+                    "Unknown command: {}. Connect without one to go to the lobby, or with `table` \
+                     to take a seat at the first free table.\r\n",
+                    command
+                ));
+                1
+            }
+        };
+        output.close(status);
+    }
+
+    /// Shows the lobby until the player starts a game or quits.
+    fn visit_lobby(&self, mut session: Session) {
+        let mut lobby = Lobby::new();
+        let mut drawn = None;
+        loop {
+            let tables = self.tables();
+            if drawn.as_ref() != Some(&tables) {
+                if draw_lobby(&session, &lobby, &tables).is_err() {
+                    return;
+                }
+                drawn = Some(tables.clone());
+            }
+
+            let key = select! {
+                recv(session.keys) -> event => match event {
+                    Ok(Event::Key(key)) => key,
+                    Ok(Event::Resize(columns, rows)) => {
+                        session.size = Some((columns, rows));
+                        drawn = None;
+                        continue;
+                    }
+                    Ok(_) => continue,
+                    Err(_) => return,
+                },
+                default(REFRESH_INTERVAL) => continue,
+            };
+            drawn = None;
+
+            let (id, password) = match lobby.on_key(key, &tables, self.table_players) {
+                None => continue,
+                Some(Choice::Quit) => return,
+                Some(Choice::PlayAlone) => return self.play_alone(session),
+                Some(Choice::Join { id, password }) => (id, password),
+                Some(Choice::Open {
+                    players,
+                    rules,
+                    password,
+                }) => {
+                    let id = self.open_table(&session.user, players, rules, password.clone());
+                    (id, password)
+                }
+            };
+            let (seat, game) = Seat::new(&session);
+            match self.take_seat(id, password.as_deref(), seat) {
+                Ok(()) => {
+                    if !self.sit_at_table(&mut session, id, game) {
+                        return;
+                    }
+                }
+                Err(e) => lobby.set_message(e.to_string()),
+            }
+        }
+    }
+
+    fn play_alone(&self, session: Session) {
+        let (events, game_events) = crossbeam_channel::unbounded();
+        let height = session.height();
+        let keys = session.keys;
+        thread::spawn(move || forward(keys, 0, events));
+
+        let screen = Screen::new(Box::new(ChannelWriter::new(session.output)), height);
+        // the game ends early if the client goes away, and there is no one
+        // left to tell
+        let _ = new_game(
+            session.charset,
+            self.animation,
+            Rules::Standard,
+            vec![session.user],
+        )
+        .play(vec![screen], game_events);
+    }
+
+    /// Takes a seat at the first table with one free and no password,
+    /// opening a table if there is none, and waits there. Returns whether
+    /// the player left for the lobby.
+    fn sit_at_free_table(&self, session: &mut Session) -> bool {
+        loop {
+            let free = self
+                .tables()
+                .into_iter()
+                .find(|table| !table.playing && !table.locked);
+            let id = match free {
+                Some(table) => table.id,
+                None => self.open_table(&session.user, self.table_players, Rules::Standard, None),
+            };
+            let (seat, game) = Seat::new(session);
+            // someone may have taken the last seat in the meantime
+            if self.take_seat(id, None, seat).is_ok() {
+                return self.sit_at_table(session, id, game);
+            }
+        }
+    }
+
+    /// Waits at table `id` until it is full, and then passes keys on to the
+    /// game. Returns whether the player left for the lobby instead.
+    fn sit_at_table(&self, session: &mut Session, id: u64, game: Receiver<Started>) -> bool {
+        let mut drawn = None;
+        loop {
+            let table = self.table(id);
+            // the table is full once the game starts
+            if game.is_empty() && drawn != table {
+                if let Some(table) = &table {
+                    let _ = self.draw_waiting(&session.output, table);
+                }
+                drawn = table;
+            }
+
+            select! {
+                recv(game) -> game => {
+                    if let Ok((screen, events)) = game {
+                        forward(session.keys.clone(), screen, events);
+                    }
+                    return false;
+                }
+                recv(session.keys) -> event => match event {
+                    Ok(Event::Key(key)) if is_quit_key(key) => {
+                        // once the game has started, it gets the keys instead
+                        if self.leave_table(id, &session.output) {
+                            return true;
+                        }
+                    }
+                    Ok(Event::Resize(columns, rows)) => {
+                        session.size = Some((columns, rows));
+                        self.resize_seat(id, &session.output, rows);
+                        drawn = None;
+                    }
+                    Ok(_) => (),
+                    Err(_) => {
+                        self.leave_table(id, &session.output);
+                        return false;
+                    }
+                },
+                default(REFRESH_INTERVAL) => (),
+            }
+        }
+    }
+
+    fn tables(&self) -> Vec<TableSummary> {
+        let tables = self.tables.lock().unwrap();
+        tables
+            .tables
+            .iter()
+            .map(|(id, table)| table.summary(*id))
+            .collect()
+    }
+
+    fn table(&self, id: u64) -> Option<TableSummary> {
+        let tables = self.tables.lock().unwrap();
+        tables.tables.get(&id).map(|table| table.summary(id))
+    }
+
+    /// Opens a table with no one seated yet, and returns its ID.
+    fn open_table(
+        &self,
+        host: &str,
+        players: usize,
+        rules: Rules,
+        password: Option<String>,
+    ) -> u64 {
+        let mut tables = self.tables.lock().unwrap();
+        let id = tables.next_id;
+        tables.next_id += 1;
+        tables.tables.insert(
+            id,
+            Table {
+                host: host.to_string(),
+                rules,
+                players,
+                password,
+                seats: Vec::new(),
+                playing: false,
+            },
+        );
+        id
+    }
+
+    /// Seats a player at table `id`, and starts its game once all the seats
+    /// are taken.
+    fn take_seat(&self, id: u64, password: Option<&str>, seat: Seat) -> Result<()> {
+        let mut tables = self.tables.lock().unwrap();
+        let table = match tables.tables.get_mut(&id) {
+            Some(table) => table,
+            None => bail!("The table has closed"),
+        };
+        if table.playing {
+            bail!("The game at this table has already started");
+        }
+        if table.password.is_some() && table.password.as_deref() != password {
+            bail!("Wrong password");
+        }
+        table.seats.push(seat);
+        if table.seats.len() < table.players {
+            return Ok(());
+        }
+
+        table.playing = true;
+        let seats = table.seats.clone();
+        let rules = table.rules;
+        let animation = self.animation;
+        let tables = self.tables.clone();
+        thread::spawn(move || {
+            play_at_table(seats, rules, animation);
+            tables.lock().unwrap().tables.remove(&id);
+        });
+        Ok(())
+    }
+
+    /// Takes a player waiting at table `id` off it, and returns whether they
+    /// were still waiting. A table is closed when the last player leaves.
+    fn leave_table(&self, id: u64, output: &Arc<Output>) -> bool {
+        let mut tables = self.tables.lock().unwrap();
+        let table = match tables.tables.get_mut(&id) {
+            Some(table) if !table.playing => table,
+            _ => return false,
+        };
+        let before = table.seats.len();
+        table
+            .seats
+            .retain(|seat| !Arc::ptr_eq(&seat.output, output));
+        let left = table.seats.len() < before;
+        if table.seats.is_empty() {
+            tables.tables.remove(&id);
+        }
+        left
+    }
+
+    fn resize_seat(&self, id: u64, output: &Arc<Output>, height: u16) {
+        let mut tables = self.tables.lock().unwrap();
+        if let Some(table) = tables.tables.get_mut(&id) {
+            for seat in table.seats.iter_mut() {
+                if Arc::ptr_eq(&seat.output, output) {
+                    seat.height = height;
+                }
+            }
+        }
+    }
+
+    fn draw_waiting(&self, output: &Arc<Output>, table: &TableSummary) -> Result<()> {
+        let mut w = ChannelWriter::new(output.clone());
+        crossterm::queue!(
+            w,
+            terminal::Clear(terminal::ClearType::All),
+            cursor::Hide,
+            cursor::MoveTo(0, 0),
+            style::Print(format!(
+                "Table {} ({})    Waiting for players: {} / {}",
+                table.id,
+                table.rules.label(),
+                table.seated.len(),
+                table.players
+            ))
+        )?;
+        for (name, y) in table.seated.iter().zip(2..) {
+            crossterm::queue!(w, cursor::MoveTo(2, y), style::Print(name))?;
+        }
+        crossterm::queue!(
+            w,
+            cursor::MoveTo(0, table.seated.len() as u16 + 3),
+            style::Print("Esc or Q: back to the lobby")
+        )?;
+        w.flush()?;
+        Ok(())
+    }
+}
+
+fn new_game(charset: Charset, animation: bool, rules: Rules, names: Vec<String>) -> Game {
+    GameBuilder::new()
+        .charset(charset)
+        .animation(animation)
+        .hints(rules.hints())
+        .players(names.len())
+        .player_names(names)
+        .build()
+}
+
+fn play_at_table(seats: Vec<Seat>, rules: Rules, animation: bool) {
+    let (events, game_events) = crossbeam_channel::unbounded();
+    for (i, seat) in seats.iter().enumerate() {
+        let _ = seat.started.send((i, events.clone()));
+    }
+    drop(events);
+
+    // draw for the least capable of the terminals
+    let charset = if seats.iter().any(|seat| seat.charset == Charset::Ascii) {
+        Charset::Ascii
+    } else {
+        Charset::Unicode
+    };
+    let screens = seats
+        .iter()
+        .map(|seat| {
+            Screen::new(
+                Box::new(ChannelWriter::new(seat.output.clone())),
+                seat.height,
+            )
+        })
+        .collect();
+    let names = seats.iter().map(|seat| seat.name.clone()).collect();
+    let _ = new_game(charset, animation, rules, names).play(screens, game_events);
+
+    for seat in &seats {
+        seat.output.close(0);
+    }
+}
+
+fn draw_lobby(session: &Session, lobby: &Lobby, tables: &[TableSummary]) -> Result<()> {
+    let mut w = ChannelWriter::new(session.output.clone());
+    lobby.draw(&mut w, tables, session.height())?;
+    w.flush()?;
+    Ok(())
+}
+
+/// Passes the keys typed at the terminal of `screen` on to a game, and quits
+/// the game once the terminal is gone.
+fn forward(keys: Receiver<Event>, screen: usize, events: Sender<(usize, Event)>) {
+    for key in keys {
+        if events.send((screen, key)).is_err() {
+            return;
+        }
+    }
+    let quit = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+    let _ = events.send((screen, Event::Key(quit)));
+}
+
+fn is_quit_key(key: KeyEvent) -> bool {
+    matches!(
+        (key.modifiers, key.code),
+        (KeyModifiers::CONTROL, KeyCode::Char('c')) | (_, KeyCode::Esc) | (_, KeyCode::Char('q'))
+    )
+}
+
+/// Tables players wait at until they are full, and then play at.
+#[derive(Default)]
+struct Tables {
+    next_id: u64,
+    tables: BTreeMap<u64, Table>,
+}
+
+struct Table {
+    /// Name of the player who opened the table.
+    host: String,
+    rules: Rules,
+    players: usize,
+    password: Option<String>,
+    seats: Vec<Seat>,
+    playing: bool,
+}
+
+impl Table {
+    fn summary(&self, id: u64) -> TableSummary {
+        TableSummary {
+            id,
+            host: self.host.clone(),
+            rules: self.rules,
+            players: self.players,
+            seated: self.seats.iter().map(|seat| seat.name.clone()).collect(),
+            locked: self.password.is_some(),
+            playing: self.playing,
+        }
+    }
+}
+
+/// A player's screen index and where the game takes their key events, sent
+/// to them once the game starts.
+type Started = (usize, Sender<(usize, Event)>);
+
+/// A player at a table.
+#[derive(Clone)]
+struct Seat {
+    name: String,
+    charset: Charset,
+    height: u16,
+    output: Arc<Output>,
+    started: Sender<Started>,
+}
+
+impl Seat {
+    /// A seat for the player of `session`, and where they hear of the game
+    /// starting.
+    fn new(session: &Session) -> (Self, Receiver<Started>) {
+        let (started, game) = crossbeam_channel::bounded(1);
+        let seat = Self {
+            name: session.user.clone(),
+            charset: session.charset,
+            height: session.height(),
+            output: session.output.clone(),
+            started,
+        };
+        (seat, game)
+    }
+}
+
+/// A client's connection, with the state russh leaves to the server.
+struct Connection {
+    password: Option<String>,
+    charset: Charset,
+    sessions: Sender<(Session, String)>,
+    runtime: tokio::runtime::Handle,
+    user: String,
+    /// Whether the client has opened its session channel, which it may do
+    /// only once.
+    opened: bool,
+    channel: Option<Channel>,
+    _slot: Slot,
+}
+
+impl Connection {
+    /// Logs the client in as `user`, if the name can be shown to others.
+    fn log_in(&mut self, user: &str) -> Auth {
+        let length = user.chars().count();
+        if (1..=MAX_USER_LEN).contains(&length) && !user.chars().any(char::is_control) {
+            self.user = user.to_string();
+            Auth::Accept
+        } else {
+            self.reject()
+        }
+    }
+
+    fn reject(&self) -> Auth {
+        Auth::Reject {
+            proceed_with_methods: self.password.as_ref().map(|_| MethodSet::PASSWORD),
+        }
+    }
+
+    fn channel(&mut self, id: ChannelId) -> Option<&mut Channel> {
+        self.channel.as_mut().filter(|channel| channel.id == id)
+    }
+
+    /// Starts the session on channel `id` with `command`, unless it has
+    /// already started.
+    fn start(
+        &mut self,
+        id: ChannelId,
+        command: String,
+        connected: &mut Connected,
+    ) -> Result<(), russh::Error> {
+        let (user, charset) = (self.user.clone(), self.charset);
+        let session = match self.channel(id) {
+            Some(channel) if !channel.started => {
+                channel.started = true;
+                channel.session(&user, charset)
+            }
+            _ => return connected.channel_failure(id),
+        };
+        connected.channel_success(id)?;
+        let _ = self.sessions.send((session, command));
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl russh::server::Handler for Connection {
+    type Error = russh::Error;
+
+    async fn auth_none(&mut self, user: &str) -> Result<Auth, Self::Error> {
+        if self.password.is_some() {
+            return Ok(self.reject());
+        }
+        Ok(self.log_in(user))
+    }
+
+    async fn auth_password(&mut self, user: &str, password: &str) -> Result<Auth, Self::Error> {
+        if self.password.as_deref() != Some(password) {
+            return Ok(self.reject());
+        }
+        Ok(self.log_in(user))
+    }
+
+    async fn channel_open_session(
+        &mut self,
+        channel: russh::Channel<Msg>,
+        connected: &mut Connected,
+    ) -> Result<bool, Self::Error> {
+        if self.opened {
+            return Ok(false);
+        }
+        self.opened = true;
+        // what the client sends is passed on from the handler instead, so
+        // that it can be bounded
+        self.channel = Some(Channel::new(
+            channel.id(),
+            connected.handle(),
+            self.runtime.clone(),
+        ));
+        Ok(true)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn pty_request(
+        &mut self,
+        id: ChannelId,
+        _term: &str,
+        columns: u32,
+        rows: u32,
+        _pixel_width: u32,
+        _pixel_height: u32,
+        _modes: &[(russh::Pty, u32)],
+        connected: &mut Connected,
+    ) -> Result<(), Self::Error> {
+        match self.channel(id) {
+            Some(channel) => {
+                channel.size = Some(terminal_size(columns, rows));
+                connected.channel_success(id)
+            }
+            None => connected.channel_failure(id),
+        }
+    }
+
+    async fn window_change_request(
+        &mut self,
+        id: ChannelId,
+        columns: u32,
+        rows: u32,
+        _pixel_width: u32,
+        _pixel_height: u32,
+        _connected: &mut Connected,
+    ) -> Result<(), Self::Error> {
+        if let Some(channel) = self.channel(id) {
+            let (columns, rows) = terminal_size(columns, rows);
+            channel.size = Some((columns, rows));
+            let _ = channel.keys.try_send(Event::Resize(columns, rows));
+        }
+        Ok(())
+    }
+
+    async fn env_request(
+        &mut self,
+        id: ChannelId,
+        name: &str,
+        value: &str,
+        _connected: &mut Connected,
+    ) -> Result<(), Self::Error> {
+        if let Some(channel) = self.channel(id) {
+            if channel.env.len() < MAX_ENV_VARS {
+                channel.env.insert(name.to_string(), value.to_string());
+            }
+        }
+        Ok(())
+    }
+
+    async fn shell_request(
+        &mut self,
+        id: ChannelId,
+        connected: &mut Connected,
+    ) -> Result<(), Self::Error> {
+        self.start(id, String::new(), connected)
+    }
+
+    async fn exec_request(
+        &mut self,
+        id: ChannelId,
+        command: &[u8],
+        connected: &mut Connected,
+    ) -> Result<(), Self::Error> {
+        let command = String::from_utf8_lossy(command).into_owned();
+        self.start(id, command, connected)
+    }
+
+    fn adjust_window(&mut self, id: ChannelId, window: u32) -> u32 {
+        if let Some(channel) = self.channel(id) {
+            channel.window_adjusted = true;
+        }
+        window
+    }
+
+    async fn data(
+        &mut self,
+        id: ChannelId,
+        data: &[u8],
+        _connected: &mut Connected,
+    ) -> Result<(), Self::Error> {
+        if let Some(channel) = self.channel(id) {
+            if channel.receive(data.len()) {
+                for event in parse_keys(data) {
+                    // a client typing faster than the game reads loses keys
+                    let _ = channel.keys.try_send(event);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn channel_close(
+        &mut self,
+        id: ChannelId,
+        _connected: &mut Connected,
+    ) -> Result<(), Self::Error> {
+        if self.channel(id).is_some() {
+            self.channel = None;
+        }
+        Ok(())
+    }
+}
+
+/// One of the connections allowed at once, given back when dropped.
+struct Slot(Arc<AtomicUsize>);
+
+impl Slot {
+    fn take(connections: &Arc<AtomicUsize>) -> Self {
+        connections.fetch_add(1, Ordering::SeqCst);
+        Self(connections.clone())
+    }
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// The session channel of a connection, as the handler sees it.
+struct Channel {
+    id: ChannelId,
+    output: Arc<Output>,
+    keys: Sender<Event>,
+    /// Where the session's thread gets the keys from, until it starts.
+    session_keys: Receiver<Event>,
+    size: Option<(u16, u16)>,
+    env: HashMap<String, String>,
+    started: bool,
+    /// Bytes the client may still send, as russh counts them.
+    window: u32,
+    /// Whether russh has just given the client a full window again.
+    window_adjusted: bool,
+}
+
+impl Channel {
+    fn new(id: ChannelId, handle: Handle, runtime: tokio::runtime::Handle) -> Self {
+        let (keys, session_keys) = crossbeam_channel::bounded(MAX_QUEUED_KEYS);
+        Self {
+            id,
+            output: Arc::new(Output {
+                handle,
+                id,
+                runtime,
+                closed: AtomicBool::new(false),
+            }),
+            keys,
+            session_keys,
+            size: None,
+            env: HashMap::new(),
+            started: false,
+            window: WINDOW_SIZE,
+            window_adjusted: false,
+        }
+    }
+
+    /// Counts `len` bytes received from the client against its window, and
+    /// returns whether they fit in it. russh passes on the data that doesn't
+    /// as well, only without counting it.
+    fn receive(&mut self, len: usize) -> bool {
+        let fits = len <= self.window as usize;
+        if mem::take(&mut self.window_adjusted) {
+            self.window = WINDOW_SIZE;
+        } else if fits {
+            self.window -= len as u32;
+        }
+        fits
+    }
+
+    fn session(&self, user: &str, charset: Charset) -> Session {
+        Session {
+            user: user.to_string(),
+            output: self.output.clone(),
+            keys: self.session_keys.clone(),
+            size: self.size,
+            charset: Charset::from_locale(|name| self.env.get(name).cloned()).unwrap_or(charset),
+        }
+    }
+}
+
+impl Drop for Channel {
+    /// Whatever ended the channel, the session can't go on; dropping the
+    /// keys also ends the session's thread.
+    fn drop(&mut self) {
+        self.output.hang_up();
+    }
+}
+
+/// Columns and rows to draw on for a terminal of the size a client asked
+/// for, within what can be drawn on.
+fn terminal_size(columns: u32, rows: u32) -> (u16, u16) {
+    let clamp = |size: u32, min: u16| size.clamp(min.into(), MAX_TERMINAL_SIZE.into()) as u16;
+    (
+        clamp(columns, MIN_TERMINAL_SIZE.0),
+        clamp(rows, MIN_TERMINAL_SIZE.1),
+    )
+}
+
+/// What the thread running a session needs.
+struct Session {
+    user: String,
+    output: Arc<Output>,
+    keys: Receiver<Event>,
+    /// Columns and rows of the terminal, if the client asked for one.
+    size: Option<(u16, u16)>,
+    charset: Charset,
+}
+
+impl Session {
+    fn height(&self) -> u16 {
+        self.size.map_or(24, |(_, rows)| rows)
+    }
+}
+
+/// The sending side of a session channel, shared by the threads drawing on
+/// its terminal.
+struct Output {
+    handle: Handle,
+    id: ChannelId,
+    /// Where russh runs, for the threads outside it to wait on.
+    runtime: tokio::runtime::Handle,
+    closed: AtomicBool,
+}
+
+impl Output {
+    /// Sends terminal output, waiting for the client to make room for it.
+    fn send(&self, data: &[u8]) -> io::Result<()> {
+        for chunk in data.chunks(MAX_DATA_LEN) {
+            if self.closed.load(Ordering::SeqCst) {
+                return Err(io::ErrorKind::BrokenPipe.into());
+            }
+            self.runtime
+                .block_on(self.handle.data(self.id, CryptoVec::from_slice(chunk)))
+                .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))?;
+        }
+        Ok(())
+    }
+
+    fn print(&self, text: &str) -> io::Result<()> {
+        self.send(text.as_bytes())
+    }
+
+    /// Ends the session, telling the client how it went.
+    fn close(&self, exit_status: u32) {
+        if self.set_closed() {
+            self.runtime.block_on(async {
+                let _ = self.handle.exit_status_request(self.id, exit_status).await;
+                let _ = self.handle.eof(self.id).await;
+                let _ = self.handle.close(self.id).await;
+            });
+        }
+    }
+
+    /// Stops sending after the client has gone.
+    fn hang_up(&self) {
+        self.set_closed();
+    }
+
+    /// Marks the channel closed, and returns whether it was open.
+    fn set_closed(&self) -> bool {
+        !self.closed.swap(true, Ordering::SeqCst)
+    }
+}
+
+/// Collects what is drawn on a session's terminal and sends it on flush.
+struct ChannelWriter {
+    output: Arc<Output>,
+    buffer: Vec<u8>,
+}
+
+impl ChannelWriter {
+    fn new(output: Arc<Output>) -> Self {
+        Self {
+            output,
+            buffer: Vec::new(),
+        }
+    }
+}
+
+impl Write for ChannelWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(data);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let result = self.output.send(&self.buffer);
+        self.buffer.clear();
+        result
+    }
+}
+
+/// Turns the bytes typed at a remote terminal into key events, as crossterm
+/// does for the local one.
+fn parse_keys(data: &[u8]) -> Vec<Event> {
+    let text = String::from_utf8_lossy(data);
+    let mut chars = text.chars().peekable();
+    let mut events = Vec::new();
+
+    while let Some(c) = chars.next() {
+        let key = match c {
+            '\x1b' => match chars.peek() {
+                Some('[') | Some('O') => {
+                    let introducer = chars.next();
+                    match parse_escape_sequence(introducer == Some('O'), &mut chars) {
+                        Some(code) => KeyEvent::from(code),
+                        None => continue,
+                    }
+                }
+                _ => KeyEvent::from(KeyCode::Esc),
+            },
+            '\r' | '\n' => KeyEvent::from(KeyCode::Enter),
+            '\t' => KeyEvent::from(KeyCode::Tab),
+            '\x7f' | '\x08' => KeyEvent::from(KeyCode::Backspace),
+            '\x01'..='\x1a' => KeyEvent::new(
+                KeyCode::Char((c as u8 - 1 + b'a') as char),
+                KeyModifiers::CONTROL,
+            ),
+            c if c.is_uppercase() => KeyEvent::new(KeyCode::Char(c), KeyModifiers::SHIFT),
+            c => KeyEvent::from(KeyCode::Char(c)),
+        };
+        events.push(Event::Key(key));
+    }
+
+    events
+}
+
+/// Reads the rest of a CSI sequence, or an SS3 one, which some terminals
+/// send for the arrows and F1.
+fn parse_escape_sequence<I: Iterator<Item = char>>(
+    ss3: bool,
+    chars: &mut Peekable<I>,
+) -> Option<KeyCode> {
+    let mut parameters = String::new();
+    while let Some(c) = chars.peek() {
+        if c.is_ascii_digit() || *c == ';' {
+            parameters.push(*c);
+            chars.next();
+        } else {
+            break;
+        }
+    }
+
+    let code = match (chars.next()?, parameters.as_str()) {
+        ('A', _) => KeyCode::Up,
+        ('B', _) => KeyCode::Down,
+        ('C', _) => KeyCode::Right,
+        ('D', _) => KeyCode::Left,
+        ('H', _) => KeyCode::Home,
+        ('F', _) => KeyCode::End,
+        ('P', _) if ss3 => KeyCode::F(1),
+        ('Z', _) => KeyCode::BackTab,
+        ('~', "1") | ('~', "7") => KeyCode::Home,
+        ('~', "4") | ('~', "8") => KeyCode::End,
+        ('~', "2") => KeyCode::Insert,
+        ('~', "3") => KeyCode::Delete,
+        ('~', "5") => KeyCode::PageUp,
+        ('~', "6") => KeyCode::PageDown,
+        ('~', "11") => KeyCode::F(1),
+        _ => return None,
+    };
+    Some(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode) -> Event {
+        Event::Key(KeyEvent::from(code))
+    }
+
+    #[test]
+    fn parses_typed_keys() {
+        assert_eq!(
+            parse_keys(b"aQ\r\t\x7f\x03"),
+            vec![
+                key(KeyCode::Char('a')),
+                Event::Key(KeyEvent::new(KeyCode::Char('Q'), KeyModifiers::SHIFT)),
+                key(KeyCode::Enter),
+                key(KeyCode::Tab),
+                key(KeyCode::Backspace),
+                Event::Key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)),
+            ]
+        );
+        assert_eq!(parse_keys("é".as_bytes()), vec![key(KeyCode::Char('é'))]);
+    }
+
+    #[test]
+    fn parses_escape_sequences_among_keys() {
+        assert_eq!(
+            parse_keys(b"\x1b[A\x1bOPx\x1b[5~\x1b"),
+            vec![
+                key(KeyCode::Up),
+                key(KeyCode::F(1)),
+                key(KeyCode::Char('x')),
+                key(KeyCode::PageUp),
+                key(KeyCode::Esc),
+            ]
+        );
+        // unknown sequences are skipped whole
+        assert_eq!(parse_keys(b"\x1b[99~a"), vec![key(KeyCode::Char('a'))]);
+    }
+
+    #[test]
+    fn parses_escape_sequences() {
+        let parse = |ss3, rest: &str| parse_escape_sequence(ss3, &mut rest.chars().peekable());
+        assert_eq!(parse(false, "D"), Some(KeyCode::Left));
+        assert_eq!(parse(false, "1;5C"), Some(KeyCode::Right));
+        assert_eq!(parse(false, "3~"), Some(KeyCode::Delete));
+        assert_eq!(parse(false, "Z"), Some(KeyCode::BackTab));
+        assert_eq!(parse(true, "P"), Some(KeyCode::F(1)));
+        assert_eq!(parse(false, "P"), None);
+        assert_eq!(parse(false, "12~"), None);
+        assert_eq!(parse(false, "5"), None);
+
+        let mut chars = "Hx".chars().peekable();
+        assert_eq!(
+            parse_escape_sequence(false, &mut chars),
+            Some(KeyCode::Home)
+        );
+        assert_eq!(chars.next(), Some('x'));
+    }
+
+    #[test]
+    fn tables_wait_for_as_many_players_as_the_lobby_allows() {
+        let players = |players| SshServerBuilder::new().table_players(players).table_players;
+        assert_eq!(players(1), MIN_TABLE_PLAYERS);
+        assert_eq!(players(4), 4);
+        assert_eq!(players(40), MAX_TABLE_PLAYERS);
+    }
+
+    #[test]
+    fn terminal_sizes_are_clamped() {
+        assert_eq!(terminal_size(80, 24), (80, 24));
+        assert_eq!(terminal_size(0, 0), MIN_TERMINAL_SIZE);
+        assert_eq!(
+            terminal_size(70_000, u32::MAX),
+            (MAX_TERMINAL_SIZE, MAX_TERMINAL_SIZE)
+        );
+    }
+}
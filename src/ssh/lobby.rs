@@ -0,0 +1,390 @@
+//! The lobby screen, where players arriving over SSH find a table to join,
+//! open one of their own or start a game alone.
+
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::style::{Color, ContentStyle, StyledContent};
+use crossterm::{cursor, style, terminal};
+use std::io::Write;
+
+/// Fewest players a table may seat; one player plays alone instead.
+pub const MIN_TABLE_PLAYERS: usize = 2;
+/// Most players a table may seat.
+pub const MAX_TABLE_PLAYERS: usize = 6;
+const MAX_PASSWORD_LEN: usize = 32;
+const SELECT_SYMBOL: &str = "> ";
+/// Rows taken by everything but the list of tables.
+const CHROME_HEIGHT: u16 = 12;
+
+/// What a table plays by, chosen when it is opened.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum Rules {
+    Standard,
+    /// No hints, odds or chances of winning, so the players are on their own.
+    NoHints,
+}
+
+impl Rules {
+    pub fn label(self) -> &'static str {
+        match self {
+            Rules::Standard => "Standard",
+            Rules::NoHints => "No hints",
+        }
+    }
+
+    pub fn hints(self) -> bool {
+        self == Rules::Standard
+    }
+
+    fn next(self) -> Self {
+        match self {
+            Rules::Standard => Rules::NoHints,
+            Rules::NoHints => Rules::Standard,
+        }
+    }
+}
+
+/// What the lobby shows of a table.
+#[derive(Clone, PartialEq)]
+pub struct TableSummary {
+    pub id: u64,
+    pub host: String,
+    pub rules: Rules,
+    pub players: usize,
+    pub seated: Vec<String>,
+    pub locked: bool,
+    pub playing: bool,
+}
+
+impl TableSummary {
+    fn status(&self) -> &'static str {
+        if self.playing {
+            "Playing"
+        } else {
+            "Waiting"
+        }
+    }
+}
+
+/// What a player decided in the lobby.
+pub enum Choice {
+    Join {
+        id: u64,
+        password: Option<String>,
+    },
+    Open {
+        players: usize,
+        rules: Rules,
+        password: Option<String>,
+    },
+    PlayAlone,
+    Quit,
+}
+
+pub struct Lobby {
+    /// Table the cursor is on, kept by ID as tables come and go.
+    selected: Option<u64>,
+    form: Option<Form>,
+    /// Password being typed to join a locked table.
+    prompt: Option<(u64, String)>,
+    message: Option<String>,
+}
+
+/// Settings of a table about to be opened.
+struct Form {
+    players: usize,
+    rules: Rules,
+    password: String,
+    field: Field,
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum Field {
+    Players,
+    Rules,
+    Password,
+}
+
+impl Lobby {
+    pub fn new() -> Self {
+        Self {
+            selected: None,
+            form: None,
+            prompt: None,
+            message: None,
+        }
+    }
+
+    /// Shows `message` below the tables until the next key.
+    pub fn set_message<S: Into<String>>(&mut self, message: S) {
+        self.message = Some(message.into());
+    }
+
+    /// Handles a key, with `players` the number of seats offered for a new
+    /// table.
+    pub fn on_key(
+        &mut self,
+        key: KeyEvent,
+        tables: &[TableSummary],
+        players: usize,
+    ) -> Option<Choice> {
+        self.message = None;
+        if let (KeyModifiers::CONTROL, KeyCode::Char('c')) = (key.modifiers, key.code) {
+            return Some(Choice::Quit);
+        }
+        if let Some(form) = &mut self.form {
+            return match form.on_key(key) {
+                FormResult::Editing => None,
+                FormResult::Canceled => {
+                    self.form = None;
+                    None
+                }
+                FormResult::Done => self.form.take().map(|form| Choice::Open {
+                    players: form.players,
+                    rules: form.rules,
+                    password: Some(form.password).filter(|password| !password.is_empty()),
+                }),
+            };
+        }
+        if let Some((id, password)) = &mut self.prompt {
+            match key.code {
+                KeyCode::Esc => self.prompt = None,
+                KeyCode::Enter => {
+                    let id = *id;
+                    let password = std::mem::take(password);
+                    self.prompt = None;
+                    return Some(Choice::Join {
+                        id,
+                        password: Some(password),
+                    });
+                }
+                code => edit_text(password, code),
+            }
+            return None;
+        }
+
+        let index = self.selected_index(tables);
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => return Some(Choice::Quit),
+            KeyCode::Up | KeyCode::Char('k') | KeyCode::Char('w') => {
+                if let Some(i) = index {
+                    self.selected = Some(tables[i.saturating_sub(1)].id);
+                }
+            }
+            KeyCode::Down | KeyCode::Char('j') | KeyCode::Char('s') => {
+                if let Some(i) = index {
+                    self.selected = Some(tables[(i + 1).min(tables.len() - 1)].id);
+                }
+            }
+            KeyCode::Enter | KeyCode::Char(' ') => {
+                let table = &tables[index?];
+                if table.playing {
+                    self.set_message("The game at this table has already started");
+                } else if table.locked {
+                    self.prompt = Some((table.id, String::new()));
+                } else {
+                    return Some(Choice::Join {
+                        id: table.id,
+                        password: None,
+                    });
+                }
+            }
+            KeyCode::Char('n') => {
+                self.form = Some(Form {
+                    players: players.clamp(MIN_TABLE_PLAYERS, MAX_TABLE_PLAYERS),
+                    rules: Rules::Standard,
+                    password: String::new(),
+                    field: Field::Players,
+                })
+            }
+            KeyCode::Char('p') => return Some(Choice::PlayAlone),
+            _ => (),
+        }
+        None
+    }
+
+    fn selected_index(&self, tables: &[TableSummary]) -> Option<usize> {
+        if tables.is_empty() {
+            return None;
+        }
+        let i = tables
+            .iter()
+            .position(|table| Some(table.id) == self.selected)
+            .unwrap_or(0);
+        Some(i)
+    }
+
+    /// Draws the lobby on a terminal `height` rows high.
+    pub fn draw<W: Write>(&self, w: &mut W, tables: &[TableSummary], height: u16) -> Result<()> {
+        crossterm::queue!(
+            w,
+            terminal::Clear(terminal::ClearType::All),
+            cursor::Hide,
+            cursor::MoveTo(0, 0),
+            style::Print(
+                "Lobby    Enter: join    N: open a table    P: play alone    Esc or Q: quit"
+            ),
+            cursor::MoveTo(2, 2),
+            style::Print(format!(
+                "{:<4}{:<16}{:<12}{:<10}{}",
+                "#", "Host", "Rules", "Players", "Status"
+            )),
+        )?;
+
+        let mut y = 3;
+        if tables.is_empty() {
+            crossterm::queue!(
+                w,
+                cursor::MoveTo(2, y),
+                style::Print("No tables yet. Press N to open one.")
+            )?;
+            y += 1;
+        }
+        let index = self.selected_index(tables);
+        let rows = height.saturating_sub(CHROME_HEIGHT).max(1) as usize;
+        // scroll just far enough to keep the selected table in sight
+        let first = index.map_or(0, |i| (i + 1).saturating_sub(rows));
+        for (i, table) in tables.iter().enumerate().skip(first).take(rows) {
+            let line = format!(
+                "{:<4}{:<16}{:<12}{:<10}{}{}",
+                table.id,
+                truncate(&table.host, 15),
+                table.rules.label(),
+                format!("{} / {}", table.seated.len(), table.players),
+                table.status(),
+                if table.locked { ", password" } else { "" }
+            );
+            if Some(i) == index && self.form.is_none() {
+                let style = ContentStyle::new()
+                    .foreground(Color::Black)
+                    .background(Color::Yellow);
+                crossterm::queue!(
+                    w,
+                    cursor::MoveTo(0, y),
+                    style::Print(SELECT_SYMBOL),
+                    style::PrintStyledContent(StyledContent::new(style, line))
+                )?;
+            } else {
+                crossterm::queue!(w, cursor::MoveTo(2, y), style::Print(line))?;
+            }
+            y += 1;
+        }
+        y += 1;
+
+        if let Some(form) = &self.form {
+            form.draw(w, y)?;
+        } else if let Some((_, password)) = &self.prompt {
+            crossterm::queue!(
+                w,
+                cursor::MoveTo(0, y),
+                style::Print(format!("Password: {}", "*".repeat(password.len()))),
+                cursor::MoveTo(0, y + 2),
+                style::Print("Enter: join    Esc: cancel")
+            )?;
+        } else if let Some(table) = index.map(|i| &tables[i]) {
+            crossterm::queue!(
+                w,
+                cursor::MoveTo(0, y),
+                style::Print(format!(
+                    "At table {}: {}",
+                    table.id,
+                    table.seated.join(", ")
+                ))
+            )?;
+        }
+        if let Some(message) = &self.message {
+            crossterm::queue!(w, cursor::MoveTo(0, y + 6), style::Print(message))?;
+        }
+
+        Ok(())
+    }
+}
+
+enum FormResult {
+    Editing,
+    Canceled,
+    Done,
+}
+
+impl Form {
+    fn on_key(&mut self, key: KeyEvent) -> FormResult {
+        const FIELDS: [Field; 3] = [Field::Players, Field::Rules, Field::Password];
+        let i = FIELDS
+            .iter()
+            .position(|field| *field == self.field)
+            .unwrap();
+
+        match (self.field, key.code) {
+            (_, KeyCode::Esc) => return FormResult::Canceled,
+            (_, KeyCode::Enter) => return FormResult::Done,
+            (_, KeyCode::Up) | (_, KeyCode::BackTab) => {
+                self.field = FIELDS[(i + FIELDS.len() - 1) % FIELDS.len()];
+            }
+            (_, KeyCode::Down) | (_, KeyCode::Tab) => self.field = FIELDS[(i + 1) % FIELDS.len()],
+            (Field::Players, KeyCode::Left) | (Field::Players, KeyCode::Char('-')) => {
+                self.players = (self.players - 1).max(MIN_TABLE_PLAYERS);
+            }
+            (Field::Players, KeyCode::Right) | (Field::Players, KeyCode::Char('+')) => {
+                self.players = (self.players + 1).min(MAX_TABLE_PLAYERS);
+            }
+            (Field::Players, KeyCode::Char(c)) => {
+                if let Some(players) = c.to_digit(10) {
+                    self.players = (players as usize).clamp(MIN_TABLE_PLAYERS, MAX_TABLE_PLAYERS);
+                }
+            }
+            (Field::Rules, KeyCode::Left)
+            | (Field::Rules, KeyCode::Right)
+            | (Field::Rules, KeyCode::Char(' ')) => self.rules = self.rules.next(),
+            (Field::Password, code) => edit_text(&mut self.password, code),
+            _ => (),
+        }
+        FormResult::Editing
+    }
+
+    fn draw<W: Write>(&self, w: &mut W, y: u16) -> Result<()> {
+        let password = if self.password.is_empty() {
+            "(none)".to_string()
+        } else {
+            "*".repeat(self.password.len())
+        };
+        let fields = [
+            (Field::Players, "Players", format!("< {} >", self.players)),
+            (Field::Rules, "Rules", format!("< {} >", self.rules.label())),
+            (Field::Password, "Password", password),
+        ];
+
+        crossterm::queue!(w, cursor::MoveTo(0, y), style::Print("Open a table"))?;
+        for ((field, label, value), y) in fields.iter().zip(y + 1..) {
+            let symbol = if *field == self.field {
+                SELECT_SYMBOL
+            } else {
+                ""
+            };
+            crossterm::queue!(
+                w,
+                cursor::MoveTo(0, y),
+                style::Print(format!("{:>2}{:<10}{}", symbol, label, value))
+            )?;
+        }
+        crossterm::queue!(
+            w,
+            cursor::MoveTo(0, y + 5),
+            style::Print("Up/Down: select    Left/Right: change    Enter: open    Esc: cancel")
+        )?;
+        Ok(())
+    }
+}
+
+fn edit_text(text: &mut String, code: KeyCode) {
+    match code {
+        KeyCode::Backspace => {
+            text.pop();
+        }
+        KeyCode::Char(c) if !c.is_control() && text.len() < MAX_PASSWORD_LEN => text.push(c),
+        _ => (),
+    }
+}
+
+fn truncate(text: &str, width: usize) -> String {
+    text.chars().take(width).collect()
+}
@@ -0,0 +1,407 @@
+//! Rules of a whole game without a user interface, for embedding the engine
+//! and for serving games to other front-ends.
+
+use crate::category::{self, Category};
+use crate::dice::{Dice, DiceSet};
+use crate::scoreboard::Scoreboard;
+use crate::MAX_ROLLS;
+
+use anyhow::Result;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::sync::OnceLock;
+
+/// A game between players taking turns, with dice rolled from a seed.
+///
+/// The dice come from one generator, so a game saved with `to_json` is
+/// restored by replaying as many dice from the seed as were rolled before.
+#[derive(Clone)]
+pub struct GameState {
+    seed: u64,
+    rng: StdRng,
+    dice_rolled: u64,
+    scoreboards: Vec<Scoreboard>,
+    current_player: usize,
+    dice_set: DiceSet,
+    held: [bool; DiceSet::NUM_DICE],
+    roll: usize,
+}
+
+impl GameState {
+    pub fn new(players: usize, seed: u64) -> Result<Self> {
+        if players == 0 {
+            anyhow::bail!("A game needs at least one player");
+        }
+
+        let mut state = Self {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+            dice_rolled: 0,
+            scoreboards: vec![Scoreboard::new(); players],
+            current_player: 0,
+            dice_set: DiceSet::default(),
+            held: [false; DiceSet::NUM_DICE],
+            roll: 1,
+        };
+        state.start_turn();
+        Ok(state)
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn players(&self) -> usize {
+        self.scoreboards.len()
+    }
+
+    pub fn current_player(&self) -> usize {
+        self.current_player
+    }
+
+    pub fn scoreboards(&self) -> &[Scoreboard] {
+        &self.scoreboards
+    }
+
+    pub fn scoreboard(&self) -> &Scoreboard {
+        &self.scoreboards[self.current_player]
+    }
+
+    pub fn dice_set(&self) -> &DiceSet {
+        &self.dice_set
+    }
+
+    /// Dice kept on the next reroll.
+    pub fn held(&self) -> [bool; DiceSet::NUM_DICE] {
+        self.held
+    }
+
+    /// How many times the dice have been rolled this turn, starting from 1.
+    pub fn roll(&self) -> usize {
+        self.roll
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.scoreboards.iter().all(Scoreboard::game_is_finished)
+    }
+
+    pub fn can_reroll(&self) -> bool {
+        !self.is_finished() && self.roll < MAX_ROLLS
+    }
+
+    pub fn category_is_available(&self, category: Category) -> bool {
+        !self.is_finished()
+            && self
+                .scoreboard()
+                .category_is_available(category, &self.dice_set)
+    }
+
+    pub fn toggle_hold(&mut self, dice: usize) -> Result<()> {
+        if dice >= DiceSet::NUM_DICE {
+            anyhow::bail!("Dice must be a number from 0 to {}", DiceSet::NUM_DICE - 1);
+        }
+        self.held[dice] = !self.held[dice];
+        Ok(())
+    }
+
+    pub fn set_held(&mut self, held: [bool; DiceSet::NUM_DICE]) {
+        self.held = held;
+    }
+
+    /// Rolls the dice that aren't held, and releases them all.
+    pub fn reroll(&mut self) -> Result<()> {
+        if !self.can_reroll() {
+            anyhow::bail!("No rolls left this turn");
+        }
+        if self.held.iter().all(|held| *held) {
+            anyhow::bail!("All dice are held");
+        }
+
+        for i in 0..DiceSet::NUM_DICE {
+            if !self.held[i] {
+                self.dice_set.0[i] = self.roll_dice();
+            }
+        }
+        self.held = [false; DiceSet::NUM_DICE];
+        self.roll += 1;
+        Ok(())
+    }
+
+    /// Scores the dice in `category` for the current player, passes the turn
+    /// on and returns the points scored.
+    pub fn choose_category(&mut self, category: Category) -> Result<u32> {
+        if !self.category_is_available(category) {
+            anyhow::bail!("Category is not available: {}", category.id());
+        }
+
+        let scoreboard = &mut self.scoreboards[self.current_player];
+        let before = scoreboard.grand_total();
+        scoreboard.choose_category(category, &self.dice_set);
+        let points = scoreboard.grand_total() - before;
+
+        if !self.is_finished() {
+            // a bonus five of a kind takes a turn without filling a category,
+            // so players can finish at different times
+            loop {
+                self.current_player = (self.current_player + 1) % self.players();
+                if !self.scoreboard().game_is_finished() {
+                    break;
+                }
+            }
+            self.start_turn();
+        }
+        Ok(points)
+    }
+
+    pub fn to_json(&self) -> Value {
+        let scoreboards: Vec<_> = self
+            .scoreboards
+            .iter()
+            .map(|scoreboard| {
+                let scores: serde_json::Map<_, _> = category::CATEGORIES
+                    .iter()
+                    .filter_map(|category| {
+                        let score = scoreboard.category_score(*category)?;
+                        Some((category.id().to_string(), json!(score)))
+                    })
+                    .collect();
+                Value::Object(scores)
+            })
+            .collect();
+        let dice: Vec<_> = self.dice_set.0.iter().map(|dice| dice.pips()).collect();
+
+        json!({
+            "seed": self.seed,
+            "dice_rolled": self.dice_rolled,
+            "current_player": self.current_player,
+            "roll": self.roll,
+            "dice": dice,
+            "held": self.held,
+            "scoreboards": scoreboards,
+        })
+    }
+
+    /// Restores a game saved with `to_json`, checking its scores and the
+    /// number of dice rolled against what a game can reach. The dice in
+    /// play aren't checked against the seed.
+    pub fn from_json(value: &Value) -> Result<Self> {
+        let invalid = || anyhow::anyhow!("Invalid game state");
+        let number = |key: &str| value[key].as_u64().ok_or_else(invalid);
+
+        let seed = number("seed")?;
+        let scoreboards = value["scoreboards"]
+            .as_array()
+            .ok_or_else(invalid)?
+            .iter()
+            .map(|scores| {
+                let mut scoreboard = Scoreboard::new();
+                for (category, score) in scores.as_object().ok_or_else(invalid)? {
+                    let category = category.parse()?;
+                    let score = score.as_u64().ok_or_else(invalid)?;
+                    if !score_is_possible(category, score) {
+                        anyhow::bail!("Impossible score in {}: {}", category.id(), score);
+                    }
+                    scoreboard.set_category_score(category, score as u32);
+                }
+                Ok(scoreboard)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let mut state = Self::new(scoreboards.len(), seed)?;
+        state.scoreboards = scoreboards;
+
+        state.current_player = number("current_player")? as usize;
+        state.roll = number("roll")? as usize;
+        if state.current_player >= state.players() || !(1..=MAX_ROLLS).contains(&state.roll) {
+            return Err(invalid());
+        }
+        if !state.is_finished() && state.scoreboard().game_is_finished() {
+            return Err(invalid());
+        }
+
+        // every turn rolls all the dice, and then at least one of them on
+        // each reroll
+        let num_dice = DiceSet::NUM_DICE as u64;
+        let turns: u64 = state.scoreboards.iter().map(turns_played).sum();
+        let roll = state.roll as u64;
+        let (least, most) = if state.is_finished() {
+            (turns * num_dice, turns * num_dice * MAX_ROLLS as u64)
+        } else {
+            (
+                (turns + 1) * num_dice + roll - 1,
+                turns * num_dice * MAX_ROLLS as u64 + roll * num_dice,
+            )
+        };
+        let dice_rolled = number("dice_rolled")?;
+        if !(least..=most).contains(&dice_rolled) {
+            return Err(invalid());
+        }
+
+        // replay the dice rolled so far to get the generator to where it was
+        state.rng = StdRng::seed_from_u64(seed);
+        state.dice_rolled = 0;
+        for _ in 0..dice_rolled {
+            state.roll_dice();
+        }
+
+        let dice = value["dice"]
+            .as_array()
+            .ok_or_else(invalid)?
+            .iter()
+            .map(|pips| pips.to_string().parse::<Dice>())
+            .collect::<Result<Vec<_>>>()?;
+        state.dice_set = DiceSet::from_slice(&dice)?;
+
+        let held = value["held"].as_array().ok_or_else(invalid)?;
+        if held.len() != DiceSet::NUM_DICE {
+            return Err(invalid());
+        }
+        for (held, value) in state.held.iter_mut().zip(held) {
+            *held = value.as_bool().ok_or_else(invalid)?;
+        }
+
+        Ok(state)
+    }
+
+    fn start_turn(&mut self) {
+        for i in 0..DiceSet::NUM_DICE {
+            self.dice_set.0[i] = self.roll_dice();
+        }
+        self.held = [false; DiceSet::NUM_DICE];
+        self.roll = 1;
+    }
+
+    fn roll_dice(&mut self) -> Dice {
+        self.dice_rolled += 1;
+        self.rng.gen()
+    }
+}
+
+/// Bonus five of a kinds a saved game may have scored, far more than a game
+/// gets to, so restoring one never replays more dice than a game rolls.
+const MAX_BONUS_FIVE_OF_A_KINDS: u64 = 100;
+
+/// Whether a player can have `score` in `category`.
+fn score_is_possible(category: Category, score: u64) -> bool {
+    let five_of_a_kind = five_of_a_kind_points();
+    if category == Category::FiveOfAKind && score > five_of_a_kind {
+        return score.is_multiple_of(five_of_a_kind)
+            && score / five_of_a_kind <= 1 + MAX_BONUS_FIVE_OF_A_KINDS;
+    }
+    u32::try_from(score).is_ok_and(|score| possible_scores().contains(&(category, score)))
+}
+
+/// Points each five of a kind scores.
+fn five_of_a_kind_points() -> u64 {
+    u64::from(DiceSet::default().score(Category::FiveOfAKind))
+}
+
+/// Every score some dice get in each category.
+fn possible_scores() -> &'static HashSet<(Category, u32)> {
+    static SCORES: OnceLock<HashSet<(Category, u32)>> = OnceLock::new();
+    SCORES.get_or_init(|| {
+        let mut scores = HashSet::new();
+        let mut dice = [Dice::from(0); DiceSet::NUM_DICE];
+        for mut n in 0..Dice::NUM_FACES.pow(DiceSet::NUM_DICE as u32) {
+            for dice in &mut dice {
+                *dice = Dice::from((n % Dice::NUM_FACES) as u8);
+                n /= Dice::NUM_FACES;
+            }
+            let dice_set = DiceSet::from_slice(&dice).unwrap();
+            for category in &category::CATEGORIES {
+                scores.insert((*category, dice_set.score(*category)));
+            }
+        }
+        scores
+    })
+}
+
+/// Turns a player has taken: one for each category filled, and one for each
+/// bonus five of a kind.
+fn turns_played(scoreboard: &Scoreboard) -> u64 {
+    let five_of_a_kind = five_of_a_kind_points();
+    let bonuses = scoreboard
+        .category_score(Category::FiveOfAKind)
+        .map_or(0, |score| {
+            (u64::from(score) / five_of_a_kind).saturating_sub(1)
+        });
+    let filled = category::CATEGORIES
+        .iter()
+        .filter(|category| scoreboard.category_score(**category).is_some())
+        .count();
+    filled as u64 + bonuses
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A two player game a few turns in, saved.
+    fn saved_game() -> Value {
+        let mut state = GameState::new(2, 7).unwrap();
+        state.choose_category(Category::Chance).unwrap();
+        state.set_held([true, true, false, false, false]);
+        state.reroll().unwrap();
+        state.choose_category(Category::Chance).unwrap();
+        state.to_json()
+    }
+
+    #[test]
+    fn saved_games_restore() {
+        let saved = saved_game();
+        let mut state = GameState::from_json(&saved).unwrap();
+        assert_eq!(state.to_json(), saved);
+
+        // and the dice carry on as they would have
+        let mut original = GameState::from_json(&saved).unwrap();
+        state.reroll().unwrap();
+        original.reroll().unwrap();
+        assert_eq!(state.dice_set(), original.dice_set());
+    }
+
+    #[test]
+    fn impossible_scores_are_rejected() {
+        let mut saved = saved_game();
+        // one more turn for one more category
+        saved["dice_rolled"] = json!(20);
+        for score in [7, 4_294_967_296_u64] {
+            saved["scoreboards"][0]["Sixes"] = json!(score);
+            assert!(GameState::from_json(&saved).is_err());
+        }
+        saved["scoreboards"][0]["Sixes"] = json!(18);
+        GameState::from_json(&saved).unwrap();
+
+        saved["scoreboards"][0]["FiveOfAKind"] = json!(150);
+        saved["dice_rolled"] = json!(35);
+        GameState::from_json(&saved).unwrap();
+        saved["scoreboards"][0]["FiveOfAKind"] = json!(75);
+        assert!(GameState::from_json(&saved).is_err());
+    }
+
+    #[test]
+    fn dice_rolled_must_fit_the_turns() {
+        let mut saved = saved_game();
+        // two turns of 5 to 15 dice, and 5 for the roll in play
+        for (dice_rolled, valid) in [(14, false), (15, true), (35, true), (36, false)] {
+            saved["dice_rolled"] = json!(dice_rolled);
+            assert_eq!(GameState::from_json(&saved).is_ok(), valid);
+        }
+        saved["dice_rolled"] = json!(u64::MAX);
+        assert!(GameState::from_json(&saved).is_err());
+    }
+
+    #[test]
+    fn current_player_must_have_a_turn_left() {
+        let mut saved = saved_game();
+        // chance, the last category, is filled already
+        for category in &category::CATEGORIES[..12] {
+            saved["scoreboards"][0][category.id()] = json!(0);
+        }
+        saved["dice_rolled"] = json!(5 * (category::CATEGORIES.len() + 2));
+        assert!(GameState::from_json(&saved).is_err());
+        saved["current_player"] = json!(1);
+        GameState::from_json(&saved).unwrap();
+    }
+}
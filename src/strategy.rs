@@ -0,0 +1,116 @@
+use crate::category::{self, Category};
+use crate::dice::DiceSet;
+use crate::heuristic::{HeuristicStrategy, Weights};
+use crate::rollout::{BasePolicy, Rollout};
+use crate::scoreboard::Scoreboard;
+use crate::solver::{Objective, SolverStrategy};
+use crate::MAX_ROLLS;
+
+use rand::RngCore;
+use std::str::FromStr;
+
+/// What a strategy gets to see when it's asked for a move.
+pub struct Turn<'a> {
+    pub dice_set: &'a DiceSet,
+    /// How many times the dice have been rolled this turn, starting from 1.
+    pub roll: usize,
+    pub scoreboard: &'a Scoreboard,
+    /// The other players' scoreboards, in turn order.
+    pub opponents: &'a [&'a Scoreboard],
+}
+
+impl Turn<'_> {
+    /// Whether the rules allow `decision`: a re-roll of at least one die
+    /// before the last roll, or a score in an available category.
+    pub fn allows(&self, decision: Decision) -> bool {
+        match decision {
+            Decision::Reroll(rerolled) => self.roll < MAX_ROLLS && rerolled.contains(&true),
+            Decision::Score(category) => self
+                .scoreboard
+                .category_is_available(category, self.dice_set),
+        }
+    }
+
+    /// The first category the dice can be scored in, for a strategy that
+    /// doesn't make a legal move.
+    pub fn first_available(&self) -> Category {
+        category::CATEGORIES
+            .iter()
+            .copied()
+            .find(|category| {
+                self.scoreboard
+                    .category_is_available(*category, self.dice_set)
+            })
+            .unwrap()
+    }
+}
+
+/// A way of playing the game, for bots. Strategies are `Send` so that bots
+/// can think on a thread of their own while the game goes on.
+pub trait Strategy: Send {
+    /// What to do with the dice: re-roll some of them, only while they can
+    /// still be rolled again, or score them in an available category. Any
+    /// chances the strategy takes are drawn from `rng`, so that seeded games
+    /// play out the same way again.
+    fn decide(&mut self, turn: &Turn, rng: &mut dyn RngCore) -> Decision;
+
+    /// Why the strategy gave up playing properly, if it has, such as an
+    /// external program that exited.
+    fn failure(&self) -> Option<String> {
+        None
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Decision {
+    /// Roll the marked dice again.
+    Reroll([bool; DiceSet::NUM_DICE]),
+    Score(Category),
+}
+
+/// A built-in strategy, as named on the command line.
+#[derive(Copy, Clone, PartialEq)]
+pub enum Engine {
+    Solver(Objective),
+    Rollout(Rollout),
+    Policy(BasePolicy),
+    Heuristic(Weights),
+}
+
+impl FromStr for Engine {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        if s == "optimal" {
+            Ok(Engine::Solver(Objective::ExpectedScore))
+        } else if s == "heuristic" {
+            Ok(Engine::Heuristic(Weights::default()))
+        } else if let Some(profile) = s.strip_prefix("heuristic:") {
+            Ok(Engine::Heuristic(Weights::load(profile)?))
+        } else if s.starts_with("rollout") {
+            Ok(Engine::Rollout(s.parse()?))
+        } else if let Ok(policy) = s.parse() {
+            Ok(Engine::Policy(policy))
+        } else if let Ok(objective) = s.parse() {
+            Ok(Engine::Solver(objective))
+        } else {
+            Err(anyhow::anyhow!(
+                "Unknown strategy: {} (use `expected` or `optimal`, `target:<score>`, `win`, \
+                 `greedy`, `random`, `heuristic[:<profile>]` or \
+                 `rollout[:<simulations>[:<policy>]]`)",
+                s
+            ))
+        }
+    }
+}
+
+impl Engine {
+    pub fn strategy(self) -> Box<dyn Strategy> {
+        match self {
+            Engine::Solver(objective) => Box::new(SolverStrategy::new(objective)),
+            Engine::Rollout(rollout) => Box::new(rollout),
+            Engine::Policy(policy) => Box::new(policy),
+            Engine::Heuristic(weights) => Box::new(HeuristicStrategy::new(weights)),
+        }
+    }
+}
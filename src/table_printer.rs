@@ -1,3 +1,5 @@
+use crate::charset::Charset;
+
 use anyhow::Result;
 use crossterm::style::{Color, ContentStyle, StyledContent};
 use crossterm::{cursor, style};
@@ -6,6 +8,7 @@ use std::io;
 
 const SELECT_SYMBOL: &str = "> ";
 const HIGHLIGHT_COLOR: Color = Color::Yellow;
+const DIM_COLOR: Color = Color::DarkGrey;
 
 pub struct TablePrinter<W: io::Write> {
     writer: W,
@@ -14,6 +17,8 @@ pub struct TablePrinter<W: io::Write> {
     width: usize,
     key_width: usize,
     value_width: usize,
+    note_width: usize,
+    charset: Charset,
     indent: u16,
 }
 
@@ -23,14 +28,7 @@ impl<W: io::Write> TablePrinter<W> {
         DK: fmt::Display,
         DV: fmt::Display,
     {
-        crossterm::queue!(
-            self.writer,
-            cursor::MoveTo(self.x, self.y),
-            style::Print(self.format(key, value, ""))
-        )?;
-
-        self.y += 1;
-        Ok(self)
+        self.styled_item(key, value, "", ContentStyle::new())
     }
 
     pub fn highlighted_item<DK, DV>(&mut self, key: DK, value: DV) -> Result<&mut Self>
@@ -39,16 +37,16 @@ impl<W: io::Write> TablePrinter<W> {
         DV: fmt::Display,
     {
         let style = ContentStyle::new().foreground(HIGHLIGHT_COLOR);
-        let styled = StyledContent::new(style, self.format(key, value, ""));
-
-        crossterm::queue!(
-            self.writer,
-            cursor::MoveTo(self.x, self.y),
-            style::PrintStyledContent(styled)
-        )?;
+        self.styled_item(key, value, "", style)
+    }
 
-        self.y += 1;
-        Ok(self)
+    pub fn dimmed_item<DK, DV>(&mut self, key: DK, value: DV) -> Result<&mut Self>
+    where
+        DK: fmt::Display,
+        DV: fmt::Display,
+    {
+        let style = ContentStyle::new().foreground(DIM_COLOR);
+        self.styled_item(key, value, "", style)
     }
 
     pub fn selected_item<DK, DV>(&mut self, key: DK, value: DV) -> Result<&mut Self>
@@ -59,15 +57,38 @@ impl<W: io::Write> TablePrinter<W> {
         let style = ContentStyle::new()
             .foreground(Color::Black)
             .background(HIGHLIGHT_COLOR);
-        let styled = StyledContent::new(style, self.format(key, value, SELECT_SYMBOL));
+        self.styled_item(key, value, SELECT_SYMBOL, style)
+    }
+
+    pub fn selected_dimmed_item<DK, DV>(&mut self, key: DK, value: DV) -> Result<&mut Self>
+    where
+        DK: fmt::Display,
+        DV: fmt::Display,
+    {
+        let style = ContentStyle::new()
+            .foreground(Color::White)
+            .background(DIM_COLOR);
+        self.styled_item(key, value, SELECT_SYMBOL, style)
+    }
+
+    /// Row the next item goes on.
+    pub fn y(&self) -> u16 {
+        self.y
+    }
+
+    /// Prints a note to the right of the next item.
+    pub fn note<D>(&mut self, note: D) -> Result<&mut Self>
+    where
+        D: fmt::Display,
+    {
+        let note = format!("{:note_width$}", note, note_width = self.note_width);
 
         crossterm::queue!(
             self.writer,
-            cursor::MoveTo(self.x, self.y),
-            style::PrintStyledContent(styled)
+            cursor::MoveTo(self.x + self.width as u16 + 1, self.y),
+            style::Print(note)
         )?;
 
-        self.y += 1;
         Ok(self)
     }
 
@@ -91,7 +112,11 @@ impl<W: io::Write> TablePrinter<W> {
     }
 
     pub fn separator(&mut self) -> Result<&mut Self> {
-        let separator = "─".repeat(self.width);
+        let separator = match self.charset {
+            Charset::Unicode => "─",
+            Charset::Ascii => "-",
+        }
+        .repeat(self.width);
 
         crossterm::queue!(
             self.writer,
@@ -113,6 +138,29 @@ impl<W: io::Write> TablePrinter<W> {
         Ok(self)
     }
 
+    fn styled_item<DK, DV>(
+        &mut self,
+        key: DK,
+        value: DV,
+        symbol: &str,
+        style: ContentStyle,
+    ) -> Result<&mut Self>
+    where
+        DK: fmt::Display,
+        DV: fmt::Display,
+    {
+        let styled = StyledContent::new(style, self.format(key, value, symbol));
+
+        crossterm::queue!(
+            self.writer,
+            cursor::MoveTo(self.x, self.y),
+            style::PrintStyledContent(styled)
+        )?;
+
+        self.y += 1;
+        Ok(self)
+    }
+
     fn format<DK, DV>(&self, key: DK, value: DV, symbol: &str) -> String
     where
         DK: fmt::Display,
@@ -139,6 +187,8 @@ pub struct TablePrinterBuilder<W: io::Write> {
     width: usize,
     key_width: usize,
     value_width: usize,
+    note_width: usize,
+    charset: Charset,
 }
 
 impl<W: io::Write> TablePrinterBuilder<W> {
@@ -150,6 +200,8 @@ impl<W: io::Write> TablePrinterBuilder<W> {
             width: 10,
             key_width: 5,
             value_width: 5,
+            note_width: 0,
+            charset: Charset::Unicode,
         }
     }
 
@@ -161,6 +213,8 @@ impl<W: io::Write> TablePrinterBuilder<W> {
             width: self.width,
             key_width: self.key_width,
             value_width: self.value_width,
+            note_width: self.note_width,
+            charset: self.charset,
             indent: 0,
         }
     }
@@ -189,4 +243,14 @@ impl<W: io::Write> TablePrinterBuilder<W> {
         self.value_width = value_width;
         self
     }
+
+    pub fn note_width(mut self, note_width: usize) -> Self {
+        self.note_width = note_width;
+        self
+    }
+
+    pub fn charset(mut self, charset: Charset) -> Self {
+        self.charset = charset;
+        self
+    }
 }
@@ -0,0 +1,365 @@
+use crate::simulator::{self, Sum};
+use crate::strategy::Strategy;
+
+use anyhow::{Context, Result};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::thread;
+
+/// Makes a fresh instance of an entrant's strategy for each thread.
+pub type Factory = Box<dyn Fn() -> Result<Box<dyn Strategy>> + Send + Sync>;
+
+/// Round-robin two-player matches between strategies.
+///
+/// Game `k` of every pairing uses the same dice: each seat rolls from its own
+/// generator seeded from `seed` and `k / 2`, and odd games swap the seats, so
+/// luck cancels out between entrants. Moves aren't timed here, so that the
+/// same seed plays out the same way however busy the machine is; bots
+/// running as separate programs keep to the time limit they are spawned with.
+pub struct Tournament {
+    names: Vec<String>,
+    factories: Vec<Factory>,
+    games: usize,
+    seed: u64,
+    threads: usize,
+}
+
+pub struct TournamentBuilder {
+    names: Vec<String>,
+    factories: Vec<Factory>,
+    games: usize,
+    seed: u64,
+    threads: usize,
+}
+
+impl Default for TournamentBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TournamentBuilder {
+    pub fn new() -> Self {
+        Self {
+            names: Vec::new(),
+            factories: Vec::new(),
+            games: 1000,
+            seed: 0,
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+        }
+    }
+
+    pub fn build(self) -> Tournament {
+        Tournament {
+            names: self.names,
+            factories: self.factories,
+            games: self.games,
+            seed: self.seed,
+            threads: self.threads,
+        }
+    }
+
+    pub fn entrant<S: Into<String>>(mut self, name: S, factory: Factory) -> Self {
+        self.names.push(name.into());
+        self.factories.push(factory);
+        self
+    }
+
+    /// Games played by each pair of entrants.
+    pub fn games(mut self, games: usize) -> Self {
+        self.games = games;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+}
+
+/// Results of one pair of entrants, from the first one's point of view.
+#[derive(Clone)]
+pub struct Pairing {
+    pub first: usize,
+    pub second: usize,
+    pub wins: usize,
+    pub losses: usize,
+    pub draws: usize,
+    /// First entrant's score minus the second one's in each game.
+    pub score_difference: Sum,
+}
+
+impl Pairing {
+    /// Two-sided p-value of a sign test on the decided games, against both
+    /// entrants being equally strong.
+    pub fn p_value(&self) -> f64 {
+        let decided = (self.wins + self.losses) as f64;
+        if decided == 0.0 {
+            return 1.0;
+        }
+        let z = (self.wins as f64 - self.losses as f64) / decided.sqrt();
+        erfc(z.abs() / std::f64::consts::SQRT_2)
+    }
+
+    fn merge(&mut self, other: &Pairing) {
+        self.wins += other.wins;
+        self.losses += other.losses;
+        self.draws += other.draws;
+        self.score_difference.merge(other.score_difference);
+    }
+}
+
+pub struct Standings {
+    pub names: Vec<String>,
+    pub scores: Vec<Sum>,
+    /// Wins of each entrant, counting draws as half.
+    pub wins: Vec<f64>,
+    pub elo: Vec<f64>,
+    pub pairings: Vec<Pairing>,
+    /// Why each entrant stopped playing properly, if it did in any game.
+    pub failures: Vec<Option<String>>,
+}
+
+impl Tournament {
+    pub fn run(&self) -> Result<Standings> {
+        let n = self.names.len();
+        if n < 2 {
+            anyhow::bail!("A tournament needs at least 2 entrants");
+        }
+
+        let empty_pairings: Vec<Pairing> = (0..n)
+            .flat_map(|first| {
+                (first + 1..n).map(move |second| Pairing {
+                    first,
+                    second,
+                    wins: 0,
+                    losses: 0,
+                    draws: 0,
+                    score_difference: Sum::default(),
+                })
+            })
+            .collect();
+
+        let results = thread::scope(|scope| {
+            let handles: Vec<_> = (0..self.threads)
+                .map(|thread| {
+                    let mut pairings = empty_pairings.clone();
+                    scope.spawn(move || -> Result<_> {
+                        let mut strategies = self
+                            .factories
+                            .iter()
+                            .map(|factory| factory())
+                            .collect::<Result<Vec<_>>>()?;
+                        let mut scores = vec![Sum::default(); n];
+
+                        for pairing in &mut pairings {
+                            for game in (thread..self.games).step_by(self.threads) {
+                                let totals = self.play(
+                                    &mut strategies,
+                                    pairing.first,
+                                    pairing.second,
+                                    game,
+                                )?;
+                                scores[pairing.first].add(totals[0] as f64);
+                                scores[pairing.second].add(totals[1] as f64);
+                                match totals[0].cmp(&totals[1]) {
+                                    std::cmp::Ordering::Greater => pairing.wins += 1,
+                                    std::cmp::Ordering::Less => pairing.losses += 1,
+                                    std::cmp::Ordering::Equal => pairing.draws += 1,
+                                }
+                                pairing
+                                    .score_difference
+                                    .add(totals[0] as f64 - totals[1] as f64);
+                            }
+                        }
+                        let failures: Vec<_> = strategies
+                            .iter()
+                            .map(|strategy| strategy.failure())
+                            .collect();
+                        Ok((scores, pairings, failures))
+                    })
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect::<Result<Vec<_>>>()
+        })?;
+
+        let mut scores = vec![Sum::default(); n];
+        let mut pairings = empty_pairings;
+        let mut failures = vec![None; n];
+        for (thread_scores, thread_pairings, thread_failures) in results {
+            for (failure, thread_failure) in failures.iter_mut().zip(thread_failures) {
+                if failure.is_none() {
+                    *failure = thread_failure;
+                }
+            }
+            for (total, sum) in scores.iter_mut().zip(thread_scores) {
+                total.merge(sum);
+            }
+            for (total, pairing) in pairings.iter_mut().zip(thread_pairings.iter()) {
+                total.merge(pairing);
+            }
+        }
+
+        let mut wins = vec![0.0; n];
+        for pairing in &pairings {
+            let draws = pairing.draws as f64 / 2.0;
+            wins[pairing.first] += pairing.wins as f64 + draws;
+            wins[pairing.second] += pairing.losses as f64 + draws;
+        }
+
+        Ok(Standings {
+            names: self.names.clone(),
+            scores,
+            wins,
+            elo: elo_ratings(n, &pairings),
+            pairings,
+            failures,
+        })
+    }
+
+    /// Plays game number `game` between `first` and `second`, and returns
+    /// their scores in that order. An entrant making a move the rules don't
+    /// allow stops the tournament, since its results would mean nothing.
+    fn play(
+        &self,
+        strategies: &mut [Box<dyn Strategy>],
+        first: usize,
+        second: usize,
+        game: usize,
+    ) -> Result<[u32; 2]> {
+        let context = || {
+            format!(
+                "Game {} between {} and {}",
+                game + 1,
+                self.names[first],
+                self.names[second]
+            )
+        };
+        let (left, right) = strategies.split_at_mut(second);
+        let (first, second) = (left[first].as_mut(), right[0].as_mut());
+
+        let mut rngs: Vec<StdRng> = (0..2)
+            .map(|seat| StdRng::seed_from_u64(seat_seed(self.seed, game / 2, seat)))
+            .collect();
+        if game.is_multiple_of(2) {
+            let totals = simulator::play_game_with_dice(&mut [first, second], &mut rngs)
+                .with_context(context)?;
+            Ok([totals[0], totals[1]])
+        } else {
+            let totals = simulator::play_game_with_dice(&mut [second, first], &mut rngs)
+                .with_context(context)?;
+            Ok([totals[1], totals[0]])
+        }
+    }
+}
+
+fn seat_seed(seed: u64, round: usize, seat: u64) -> u64 {
+    let mut x = seed ^ ((round as u64) << 1 | seat).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    // splitmix64 finalizer, so that nearby rounds get unrelated seeds
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// Fits Bradley-Terry strengths to the results and puts them on the Elo
+/// scale around 1500. Every pair gets one extra draw so that an entrant who
+/// never wins still gets a finite rating.
+fn elo_ratings(n: usize, pairings: &[Pairing]) -> Vec<f64> {
+    let mut wins = vec![0.0; n];
+    let mut games = vec![vec![0.0; n]; n];
+    for pairing in pairings {
+        let (i, j) = (pairing.first, pairing.second);
+        let draws = (pairing.draws + 1) as f64 / 2.0;
+        wins[i] += pairing.wins as f64 + draws;
+        wins[j] += pairing.losses as f64 + draws;
+        let total = (pairing.wins + pairing.losses + pairing.draws + 1) as f64;
+        games[i][j] = total;
+        games[j][i] = total;
+    }
+
+    let mut strengths = vec![1.0; n];
+    for _ in 0..ELO_ITERATIONS {
+        strengths = (0..n)
+            .map(|i| {
+                let denominator: f64 = (0..n)
+                    .filter(|j| *j != i)
+                    .map(|j| games[i][j] / (strengths[i] + strengths[j]))
+                    .sum();
+                wins[i] / denominator
+            })
+            .collect();
+        let log_mean = strengths.iter().map(|s| s.ln()).sum::<f64>() / n as f64;
+        for strength in &mut strengths {
+            *strength /= log_mean.exp();
+        }
+    }
+
+    strengths
+        .iter()
+        .map(|strength| 1500.0 + 400.0 * strength.log10())
+        .collect()
+}
+
+const ELO_ITERATIONS: usize = 1000;
+
+/// Complementary error function, accurate to about 1e-7.
+fn erfc(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.5 * x.abs());
+    let y = t
+        * (-x * x - 1.265_512_23
+            + t * (1.000_023_68
+                + t * (0.374_091_96
+                    + t * (0.096_784_18
+                        + t * (-0.186_288_06
+                            + t * (0.278_868_07
+                                + t * (-1.135_203_98
+                                    + t * (1.488_515_87
+                                        + t * (-0.822_152_23 + t * 0.170_872_77)))))))))
+            .exp();
+    if x >= 0.0 {
+        y
+    } else {
+        2.0 - y
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dice::DiceSet;
+    use crate::rollout::BasePolicy;
+    use crate::strategy::{Decision, Turn};
+    use rand::RngCore;
+
+    /// Re-rolls every die, however many times the dice have been rolled.
+    struct ExtraRolls;
+
+    impl Strategy for ExtraRolls {
+        fn decide(&mut self, _turn: &Turn, _rng: &mut dyn RngCore) -> Decision {
+            Decision::Reroll([true; DiceSet::NUM_DICE])
+        }
+    }
+
+    #[test]
+    fn extra_rolls_stop_the_tournament() {
+        let tournament = TournamentBuilder::new()
+            .entrant("greedy", Box::new(|| Ok(Box::new(BasePolicy::Greedy) as _)))
+            .entrant("extra rolls", Box::new(|| Ok(Box::new(ExtraRolls) as _)))
+            .games(2)
+            .threads(1)
+            .build();
+        match tournament.run() {
+            Ok(_) => panic!("the tournament went on"),
+            Err(err) => assert!(format!("{:#}", err).contains("extra rolls")),
+        }
+    }
+}
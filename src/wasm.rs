@@ -0,0 +1,259 @@
+//! WebAssembly bindings for the browser front-end in `web/`, built for
+//! `wasm32-unknown-unknown` with `--features wasm` and bound with
+//! `wasm-bindgen --target web --out-dir web/pkg`, as the README shows.
+//!
+//! Dice are given as pips from 1 to 6, categories by their ids such as
+//! `"FullHouse"`, and players by their index from 0. Randomness comes only
+//! from the seed JavaScript passes in.
+
+use crate::category::{self, Category};
+use crate::charset::Charset;
+use crate::dice::{Dice, DiceSet};
+use crate::scoreboard::Scoreboard;
+use crate::solver::{self, Objective};
+use crate::state::GameState;
+use crate::strategy::Decision;
+use crate::MAX_ROLLS;
+
+use serde_json::json;
+use wasm_bindgen::prelude::*;
+
+fn js_error(e: anyhow::Error) -> JsError {
+    JsError::new(&e.to_string())
+}
+
+fn parse_category(category: &str) -> Result<Category, JsError> {
+    category.parse().map_err(js_error)
+}
+
+fn parse_dice(pips: u8) -> Result<Dice, JsError> {
+    pips.to_string().parse().map_err(js_error)
+}
+
+fn charset(ascii: bool) -> Charset {
+    if ascii {
+        Charset::Ascii
+    } else {
+        Charset::Unicode
+    }
+}
+
+/// Ids of all categories, in the order of the score table.
+#[wasm_bindgen]
+pub fn categories() -> Vec<String> {
+    category::CATEGORIES
+        .iter()
+        .map(|category| category.id().to_string())
+        .collect()
+}
+
+#[wasm_bindgen]
+pub fn upper_section() -> Vec<String> {
+    category::UPPER_SECTION
+        .iter()
+        .map(|category| category.id().to_string())
+        .collect()
+}
+
+#[wasm_bindgen]
+pub fn lower_section() -> Vec<String> {
+    category::LOWER_SECTION
+        .iter()
+        .map(|category| category.id().to_string())
+        .collect()
+}
+
+/// Label of a category in the score table.
+#[wasm_bindgen]
+pub fn category_label(category: &str, ascii: bool) -> Result<String, JsError> {
+    Ok(parse_category(category)?.label(charset(ascii)).to_string())
+}
+
+/// Picture of a die, the same as in the terminal.
+#[wasm_bindgen]
+pub fn dice_face(pips: u8, ascii: bool) -> Result<String, JsError> {
+    Ok(parse_dice(pips)?.face(charset(ascii)).to_string())
+}
+
+#[wasm_bindgen]
+pub fn score_dice(dice: &[u8], category: &str) -> Result<u32, JsError> {
+    let dice = dice
+        .iter()
+        .map(|pips| parse_dice(*pips))
+        .collect::<Result<Vec<_>, _>>()?;
+    let dice_set = DiceSet::from_slice(&dice).map_err(js_error)?;
+    Ok(dice_set.score(parse_category(category)?))
+}
+
+#[wasm_bindgen]
+pub struct Game(GameState);
+
+#[wasm_bindgen]
+impl Game {
+    #[wasm_bindgen(constructor)]
+    pub fn new(players: usize, seed: u64) -> Result<Game, JsError> {
+        GameState::new(players, seed).map(Game).map_err(js_error)
+    }
+
+    /// Restores a game saved with `to_json`.
+    pub fn from_json(json: &str) -> Result<Game, JsError> {
+        let value = serde_json::from_str(json)?;
+        GameState::from_json(&value).map(Game).map_err(js_error)
+    }
+
+    pub fn to_json(&self) -> String {
+        self.0.to_json().to_string()
+    }
+
+    pub fn players(&self) -> usize {
+        self.0.players()
+    }
+
+    pub fn current_player(&self) -> usize {
+        self.0.current_player()
+    }
+
+    /// How many times the dice have been rolled this turn, from 1.
+    pub fn roll(&self) -> usize {
+        self.0.roll()
+    }
+
+    pub fn max_rolls(&self) -> usize {
+        MAX_ROLLS
+    }
+
+    pub fn dice(&self) -> Vec<u8> {
+        self.0.dice_set().0.iter().map(|dice| dice.pips()).collect()
+    }
+
+    pub fn is_held(&self, dice: usize) -> bool {
+        self.0.held().get(dice).copied().unwrap_or(false)
+    }
+
+    pub fn toggle_hold(&mut self, dice: usize) -> Result<(), JsError> {
+        self.0.toggle_hold(dice).map_err(js_error)
+    }
+
+    pub fn can_reroll(&self) -> bool {
+        self.0.can_reroll()
+    }
+
+    /// Rolls the dice that aren't held.
+    pub fn reroll(&mut self) -> Result<(), JsError> {
+        self.0.reroll().map_err(js_error)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.0.is_finished()
+    }
+
+    pub fn category_is_available(&self, category: &str) -> Result<bool, JsError> {
+        Ok(self.0.category_is_available(parse_category(category)?))
+    }
+
+    /// Scores the dice in `category`, passes the turn on and returns the
+    /// points scored.
+    pub fn choose_category(&mut self, category: &str) -> Result<u32, JsError> {
+        self.0
+            .choose_category(parse_category(category)?)
+            .map_err(js_error)
+    }
+
+    /// Score of a player in a category, or `undefined` if it isn't filled.
+    pub fn category_score(&self, player: usize, category: &str) -> Result<Option<u32>, JsError> {
+        Ok(self
+            .scoreboard(player)?
+            .category_score(parse_category(category)?))
+    }
+
+    /// Score of the current dice in `category`, before any bonus.
+    pub fn potential_score(&self, category: &str) -> Result<u32, JsError> {
+        Ok(self.0.dice_set().score(parse_category(category)?))
+    }
+
+    /// Sum of a player's upper section, excluding the bonus.
+    pub fn basic_total(&self, player: usize) -> Result<u32, JsError> {
+        Ok(self.scoreboard(player)?.basic_total())
+    }
+
+    /// Upper section total of a player, including the bonus.
+    pub fn upper_total(&self, player: usize) -> Result<u32, JsError> {
+        Ok(self.scoreboard(player)?.upper_total())
+    }
+
+    pub fn upper_section_bonus(&self, player: usize) -> Result<u32, JsError> {
+        Ok(self.scoreboard(player)?.upper_section_bonus())
+    }
+
+    pub fn lower_total(&self, player: usize) -> Result<u32, JsError> {
+        Ok(self.scoreboard(player)?.lower_total())
+    }
+
+    pub fn grand_total(&self, player: usize) -> Result<u32, JsError> {
+        Ok(self.scoreboard(player)?.grand_total())
+    }
+
+    fn scoreboard(&self, player: usize) -> Result<&Scoreboard, JsError> {
+        self.0
+            .scoreboards()
+            .get(player)
+            .ok_or_else(|| JsError::new(&format!("Unknown player: {}", player)))
+    }
+}
+
+/// Hints and win chances from the solver behind the `optimal` bot.
+#[wasm_bindgen]
+pub struct Solver(solver::Solver);
+
+impl Default for Solver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[wasm_bindgen]
+impl Solver {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Solver {
+        Solver(solver::Solver::new())
+    }
+
+    /// Decides the current player's move, playing for `objective`:
+    /// `"expected"`, `"target:<score>"` or `"win"`. Returns JSON in the
+    /// form of the external bot protocol: `{"hold": [...]}`, where `true`
+    /// keeps a die, or `{"category": ...}`.
+    pub fn hint(&self, game: &Game, objective: &str) -> Result<String, JsError> {
+        if game.0.is_finished() {
+            return Err(JsError::new("The game is already finished"));
+        }
+        let objective: Objective = objective.parse().map_err(js_error)?;
+        let opponents: Vec<_> = game
+            .0
+            .scoreboards()
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != game.0.current_player())
+            .map(|(_, scoreboard)| scoreboard)
+            .collect();
+
+        let decision = self.0.decide(
+            game.0.scoreboard(),
+            &opponents,
+            game.0.dice_set(),
+            MAX_ROLLS - game.0.roll(),
+            objective,
+        );
+        let hint = match decision.ok_or_else(|| JsError::new("The dice can't be scored"))? {
+            Decision::Reroll(rerolled) => json!({ "hold": rerolled.map(|rerolled| !rerolled) }),
+            Decision::Score(category) => json!({ "category": category.id() }),
+        };
+        Ok(hint.to_string())
+    }
+
+    /// Chance of each player finishing ahead of all the others, counting
+    /// ties as half wins.
+    pub fn win_chances(&self, game: &Game) -> Vec<f64> {
+        let scoreboards: Vec<_> = game.0.scoreboards().iter().collect();
+        self.0.win_chances(&scoreboards)
+    }
+}
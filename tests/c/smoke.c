@@ -0,0 +1,126 @@
+/*
+ * Plays a game through the C interface, saving and restoring it along the
+ * way. Build the library first, then compile and run this from the
+ * repository root:
+ *
+ *     cargo rustc --release --lib --crate-type cdylib
+ *     cc -Wall -Wextra -Werror -Iinclude tests/c/smoke.c -Ltarget/release -lyachtee -o target/smoke
+ *     LD_LIBRARY_PATH=target/release target/smoke
+ */
+
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "yachtee.h"
+
+#define CHECK(condition)                                                       \
+    do {                                                                       \
+        if (!(condition)) {                                                    \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__,   \
+                    #condition);                                               \
+            exit(1);                                                           \
+        }                                                                      \
+    } while (0)
+
+static char *serialize(const YachteeGame *game) {
+    size_t len = yachtee_game_serialize(game, NULL, 0);
+    char *state = malloc(len + 1);
+    CHECK(state != NULL);
+    CHECK(yachtee_game_serialize(game, state, len + 1) == len);
+    CHECK(strlen(state) == len);
+    return state;
+}
+
+/* Scores the dice in the first category the current player can take. */
+static void score_first(YachteeGame *game) {
+    bool legal[YACHTEE_NUM_CATEGORIES];
+    yachtee_game_legal_categories(game, legal);
+    for (int category = 0; category < YACHTEE_NUM_CATEGORIES; category++) {
+        if (legal[category]) {
+            CHECK(yachtee_game_score(game, category) == YACHTEE_OK);
+            return;
+        }
+    }
+    CHECK(!"no legal category");
+}
+
+int main(void) {
+    const uint8_t fives[YACHTEE_NUM_DICE] = {5, 5, 5, 5, 5};
+    CHECK(yachtee_dice_score(fives, YACHTEE_FIVE_OF_A_KIND) == 50);
+    CHECK(yachtee_dice_score(fives, YACHTEE_FIVES) == 25);
+    CHECK(yachtee_dice_score(fives, YACHTEE_NUM_CATEGORIES) == -1);
+
+    CHECK(yachtee_game_new(1, 0) == NULL);
+    YachteeGame *game = yachtee_game_new(1, 2);
+    CHECK(game != NULL);
+    CHECK(yachtee_game_players(game) == 2);
+    CHECK(yachtee_game_current_player(game) == 0);
+    CHECK(yachtee_game_roll(game) == 1);
+
+    uint8_t dice[YACHTEE_NUM_DICE];
+    yachtee_game_dice(game, dice);
+    for (int i = 0; i < YACHTEE_NUM_DICE; i++) {
+        CHECK(dice[i] >= 1 && dice[i] <= 6);
+    }
+
+    const bool hold_all[YACHTEE_NUM_DICE] = {true, true, true, true, true};
+    const bool hold_two[YACHTEE_NUM_DICE] = {true, true, false, false, false};
+    CHECK(yachtee_game_reroll(game, hold_all) == YACHTEE_ILLEGAL_MOVE);
+    CHECK(yachtee_game_reroll(game, hold_two) == YACHTEE_OK);
+    CHECK(yachtee_game_reroll(game, hold_two) == YACHTEE_OK);
+    CHECK(yachtee_game_roll(game) == YACHTEE_MAX_ROLLS);
+    CHECK(!yachtee_game_can_reroll(game));
+    CHECK(yachtee_game_reroll(game, hold_two) == YACHTEE_ILLEGAL_MOVE);
+    CHECK(yachtee_game_score(game, -1) == YACHTEE_INVALID_ARGUMENT);
+
+    CHECK(yachtee_game_score(game, YACHTEE_CHANCE) == YACHTEE_OK);
+    CHECK(yachtee_game_current_player(game) == 1);
+    CHECK(yachtee_game_category_score(game, 0, YACHTEE_CHANCE) > 0);
+    CHECK(yachtee_game_category_score(game, 1, YACHTEE_CHANCE) == -1);
+    CHECK(yachtee_game_category_score(game, 2, YACHTEE_CHANCE) == -1);
+
+    /* a restored game carries on with the same dice */
+    char *state = serialize(game);
+    YachteeGame *restored = yachtee_game_deserialize(state);
+    CHECK(restored != NULL);
+    CHECK(yachtee_game_reroll(game, hold_two) == YACHTEE_OK);
+    CHECK(yachtee_game_reroll(restored, hold_two) == YACHTEE_OK);
+    uint8_t restored_dice[YACHTEE_NUM_DICE];
+    yachtee_game_dice(game, dice);
+    yachtee_game_dice(restored, restored_dice);
+    CHECK(memcmp(dice, restored_dice, sizeof dice) == 0);
+    yachtee_game_free(restored);
+    free(state);
+
+    CHECK(yachtee_game_deserialize("") == NULL);
+    CHECK(yachtee_game_deserialize("{}") == NULL);
+    /* more dice than a game rolls */
+    CHECK(yachtee_game_deserialize(
+              "{\"seed\":1,\"dice_rolled\":18446744073709551615,"
+              "\"current_player\":0,\"roll\":1,\"dice\":[1,1,1,1,1],"
+              "\"held\":[false,false,false,false,false],"
+              "\"scoreboards\":[{}]}") == NULL);
+    /* a score no dice get */
+    CHECK(yachtee_game_deserialize(
+              "{\"seed\":1,\"dice_rolled\":10,\"current_player\":0,"
+              "\"roll\":1,\"dice\":[1,1,1,1,1],"
+              "\"held\":[false,false,false,false,false],"
+              "\"scoreboards\":[{\"Ones\":4294967295}]}") == NULL);
+
+    while (!yachtee_game_is_finished(game)) {
+        score_first(game);
+    }
+    for (size_t player = 0; player < 2; player++) {
+        CHECK(yachtee_game_grand_total(game, player) ==
+              yachtee_game_upper_total(game, player) +
+                  yachtee_game_lower_total(game, player));
+    }
+    CHECK(yachtee_game_score(game, YACHTEE_CHANCE) == YACHTEE_ILLEGAL_MOVE);
+    CHECK(yachtee_game_grand_total(game, 2) == 0);
+    yachtee_game_free(game);
+    yachtee_game_free(NULL);
+
+    puts("ok");
+    return 0;
+}
@@ -0,0 +1,141 @@
+import pytest
+
+import yachtee
+
+
+def test_dice_set_scores():
+    full_house = yachtee.DiceSet([2, 2, 3, 3, 3])
+    assert full_house.dice == [2, 2, 3, 3, 3]
+    assert full_house.score("FullHouse") == 25
+    assert full_house.score("ThreeOfAKind") == 13
+    assert full_house.score("FourOfAKind") == 0
+    assert full_house.score("Threes") == 9
+
+    straight = yachtee.DiceSet([1, 2, 3, 4, 5])
+    assert straight.scores() == {
+        "Ones": 1,
+        "Twos": 2,
+        "Threes": 3,
+        "Fours": 4,
+        "Fives": 5,
+        "Sixes": 0,
+        "ThreeOfAKind": 0,
+        "FourOfAKind": 0,
+        "FullHouse": 0,
+        "SmallStraight": 30,
+        "LargeStraight": 40,
+        "FiveOfAKind": 0,
+        "Chance": 15,
+    }
+
+
+def test_dice_set_rejects_bad_input():
+    with pytest.raises(ValueError):
+        yachtee.DiceSet([1, 2, 3, 4])
+    with pytest.raises(ValueError):
+        yachtee.DiceSet([0, 1, 2, 3, 4])
+    with pytest.raises(ValueError):
+        yachtee.DiceSet([1, 2, 3, 4, 5]).score("Sevens")
+
+
+def test_scoreboard_bonuses():
+    scoreboard = yachtee.Scoreboard()
+    for face, category in enumerate(yachtee.CATEGORIES[:6], start=1):
+        scoreboard.choose_category(category, yachtee.DiceSet([face] * 3 + [1, 1]))
+    # the upper total includes the bonus
+    assert scoreboard.upper_total() == 5 + 3 * (2 + 3 + 4 + 5 + 6) + 35
+    assert scoreboard.upper_section_bonus() == 35
+
+    scoreboard.choose_category("FiveOfAKind", yachtee.DiceSet([6] * 5))
+    assert scoreboard.category_score("FiveOfAKind") == 50
+    with pytest.raises(ValueError):
+        scoreboard.choose_category("FiveOfAKind", yachtee.DiceSet([1, 2, 3, 4, 5]))
+    assert not scoreboard.is_finished()
+
+
+def test_env_plays_a_game():
+    env = yachtee.Env(seed=1)
+    observation = env.reset(1)
+    assert len(observation) == yachtee.OBSERVATION_LEN
+
+    total = 0.0
+    done = False
+    while not done:
+        mask = env.legal_action_mask()
+        assert len(mask) == yachtee.NUM_ACTIONS
+        action = max(a for a in range(yachtee.NUM_ACTIONS) if mask[a])
+        observation, reward, done = env.step(action)
+        total += reward
+    assert total == env.scoreboard.grand_total()
+
+    with pytest.raises(ValueError):
+        env.step(yachtee.NUM_HOLDS)
+
+
+def test_env_is_reproducible():
+    first, second = yachtee.Env(), yachtee.Env()
+    assert first.reset(7) == second.reset(7)
+    assert first.dice_set.dice == second.dice_set.dice
+
+
+def test_batch_env():
+    batch = yachtee.BatchEnv([1, 2, 3])
+    assert len(batch) == 3
+    masks = batch.legal_action_masks()
+    actions = [
+        max(a for a in range(yachtee.NUM_ACTIONS) if masks[i * yachtee.NUM_ACTIONS + a])
+        for i in range(3)
+    ]
+    observations, rewards, dones = batch.step(actions)
+    assert len(observations) == 3 * yachtee.OBSERVATION_LEN
+    assert len(rewards) == len(dones) == 3
+
+
+def test_decode_action():
+    assert yachtee.decode_action(0) == {"hold": [False] * 5}
+    assert yachtee.decode_action(0b00011) == {"hold": [True, True, False, False, False]}
+    assert yachtee.decode_action(yachtee.NUM_HOLDS) == {"category": "Ones"}
+    with pytest.raises(ValueError):
+        yachtee.decode_action(yachtee.NUM_ACTIONS)
+
+
+@pytest.fixture(scope="module")
+def solver():
+    return yachtee.Solver()
+
+
+def test_solver_evaluates_positions(solver):
+    scoreboard = yachtee.Scoreboard()
+    distribution = solver.final_score_distribution(scoreboard)
+    assert sum(distribution) == pytest.approx(1.0)
+    mean = sum(score * p for score, p in enumerate(distribution))
+    assert solver.expected_final_score(scoreboard) == pytest.approx(mean)
+
+    scoreboard.choose_category("FiveOfAKind", yachtee.DiceSet([6] * 5))
+    assert solver.expected_final_score(scoreboard) > mean
+
+    win_chances = solver.win_chances([scoreboard, scoreboard])
+    assert win_chances[0] == pytest.approx(0.5)
+
+
+def test_solver_decides(solver):
+    scoreboard = yachtee.Scoreboard()
+    assert solver.decide(scoreboard, yachtee.DiceSet([4] * 5), 1) == {"category": "FiveOfAKind"}
+    decision = solver.decide(scoreboard, yachtee.DiceSet([6, 6, 6, 1, 2]), 3)
+    assert "category" in decision
+    decision = solver.decide(scoreboard, yachtee.DiceSet([6, 6, 6, 1, 2]), 1, objective="win",
+                             opponents=[yachtee.Scoreboard()])
+    assert decision["hold"][:3] == [True, True, True]
+    with pytest.raises(ValueError):
+        solver.decide(scoreboard, yachtee.DiceSet([1, 2, 3, 4, 5]), 4)
+
+
+def test_simulate():
+    scores = yachtee.simulate(["greedy", "random"], 5, seed=3)
+    assert len(scores) == 5
+    assert all(len(game) == 2 for game in scores)
+
+    scores = yachtee.simulate(["greedy"], 5, seed=3)
+    assert scores == yachtee.simulate(["greedy"], 5, seed=3)
+    with pytest.raises(ValueError):
+        yachtee.simulate(["nonsense"], 1)
@@ -0,0 +1,115 @@
+//! Tests of the SSH server with the OpenSSH client, which are skipped where
+//! `ssh` isn't installed.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+use std::thread;
+use std::time::Duration;
+use yachtee::ssh::SshServerBuilder;
+
+/// Starts a server on a free port, and returns its port.
+fn serve(name: &str, password: Option<&str>, max_connections: usize) -> String {
+    let host_key =
+        std::env::temp_dir().join(format!("yachtee-ssh-{}-{}", name, std::process::id()));
+    let server = SshServerBuilder::new()
+        .address("127.0.0.1:0")
+        .host_key(host_key)
+        .password(password.map(String::from))
+        .max_connections(max_connections)
+        .build()
+        .unwrap();
+    let address = server.address();
+    thread::spawn(move || server.run());
+    address.rsplit(':').next().unwrap().to_string()
+}
+
+fn ssh(port: &str, args: &[&str]) -> Option<Command> {
+    Command::new("ssh").arg("-V").output().ok()?;
+    let mut command = Command::new("ssh");
+    command
+        .args(["-o", "StrictHostKeyChecking=no"])
+        .args(["-o", "UserKnownHostsFile=/dev/null"])
+        .args(["-o", "BatchMode=yes"])
+        .args(["-o", "LogLevel=ERROR"])
+        .args(["-p", port, "player@127.0.0.1"])
+        .args(args);
+    Some(command)
+}
+
+fn text(output: &[u8]) -> String {
+    String::from_utf8_lossy(output).into_owned()
+}
+
+#[test]
+fn commands_need_a_terminal() {
+    let port = serve("terminal", None, 8);
+    let output = match ssh(&port, &["table"]) {
+        Some(mut ssh) => ssh.output().unwrap(),
+        None => return,
+    };
+    assert!(text(&output.stdout).starts_with("A terminal is needed"));
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn players_sit_at_a_table_and_leave() {
+    let port = serve("table", None, 8);
+    let mut ssh = match ssh(&port, &["-tt", "table"]) {
+        Some(ssh) => ssh,
+        None => return,
+    };
+    let mut child = ssh
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    // back to the lobby, and out of it
+    for key in ["q", "q"] {
+        thread::sleep(Duration::from_millis(500));
+        stdin.write_all(key.as_bytes()).unwrap();
+    }
+    let Output { status, stdout, .. } = child.wait_with_output().unwrap();
+    let stdout = text(&stdout);
+    assert!(stdout.contains("Waiting for players: 1 / 2"), "{}", stdout);
+    assert!(stdout.contains("player"));
+    assert_eq!(status.code(), Some(0));
+}
+
+#[test]
+fn passwords_are_required_when_set() {
+    let port = serve("password", Some("secret"), 8);
+    let output = match ssh(&port, &["table"]) {
+        Some(mut ssh) => ssh.output().unwrap(),
+        None => return,
+    };
+    assert!(text(&output.stderr).contains("Permission denied"));
+    assert_eq!(output.status.code(), Some(255));
+}
+
+#[test]
+fn connections_past_the_limit_are_closed() {
+    let port = serve("limit", None, 1);
+    let mut first = match ssh(&port, &["-tt", "table"]) {
+        Some(ssh) => ssh,
+        None => return,
+    };
+    let mut first = first
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .unwrap();
+    thread::sleep(Duration::from_millis(500));
+
+    let second = ssh(&port, &["table"]).unwrap().output().unwrap();
+    assert_eq!(second.status.code(), Some(255));
+
+    // the first one's leaving makes room again
+    first.kill().unwrap();
+    first.wait().unwrap();
+    thread::sleep(Duration::from_millis(500));
+    let third = ssh(&port, &["table"]).unwrap().output().unwrap();
+    assert_eq!(third.status.code(), Some(1));
+}
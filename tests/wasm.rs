@@ -0,0 +1,99 @@
+//! Tests of the WebAssembly bindings, run under Node with
+//! wasm-bindgen-test-runner as the README shows.
+
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+use wasm_bindgen_test::wasm_bindgen_test;
+use yachtee::wasm::{categories, dice_face, score_dice, Game, Solver};
+
+#[wasm_bindgen_test]
+fn scores_dice() {
+    assert_eq!(score_dice(&[2, 2, 3, 3, 3], "FullHouse").unwrap(), 25);
+    assert_eq!(score_dice(&[1, 2, 3, 4, 5], "LargeStraight").unwrap(), 40);
+    assert_eq!(score_dice(&[6, 6, 6, 6, 6], "fiveofakind").unwrap(), 50);
+    assert!(score_dice(&[1, 2, 3, 4], "Chance").is_err());
+    assert!(score_dice(&[0, 2, 3, 4, 5], "Chance").is_err());
+    assert!(score_dice(&[1, 2, 3, 4, 5], "Sevens").is_err());
+}
+
+#[wasm_bindgen_test]
+fn draws_the_terminal_dice() {
+    assert_eq!(dice_face(3, true).unwrap().lines().count(), 5);
+    assert!(dice_face(7, false).is_err());
+}
+
+#[wasm_bindgen_test]
+fn same_seed_gives_same_game() {
+    let mut first = Game::new(2, 42).unwrap();
+    let mut second = Game::new(2, 42).unwrap();
+    for _ in 0..3 {
+        assert_eq!(first.dice(), second.dice());
+        first.toggle_hold(0).unwrap();
+        second.toggle_hold(0).unwrap();
+        if first.can_reroll() {
+            first.reroll().unwrap();
+            second.reroll().unwrap();
+        }
+    }
+    assert_eq!(first.to_json(), second.to_json());
+}
+
+#[wasm_bindgen_test]
+fn plays_a_whole_game() {
+    let mut game = Game::new(2, 7).unwrap();
+    while !game.is_finished() {
+        let player = game.current_player();
+        let category = categories()
+            .into_iter()
+            .rev()
+            .find(|category| game.category_is_available(category).unwrap())
+            .unwrap();
+        let before = game.grand_total(player).unwrap();
+        let points = game.choose_category(&category).unwrap();
+        assert_eq!(game.grand_total(player).unwrap(), before + points);
+    }
+    assert!(game.reroll().is_err());
+    assert!(game.choose_category("Chance").is_err());
+}
+
+#[wasm_bindgen_test]
+fn rejects_illegal_moves() {
+    let mut game = Game::new(1, 3).unwrap();
+    for dice in 0..5 {
+        game.toggle_hold(dice).unwrap();
+    }
+    assert!(game.reroll().is_err());
+    game.toggle_hold(0).unwrap();
+    game.reroll().unwrap();
+    game.reroll().unwrap();
+    assert!(!game.can_reroll());
+    assert!(game.reroll().is_err());
+    assert!(game.toggle_hold(5).is_err());
+    assert!(Game::new(0, 1).is_err());
+}
+
+#[wasm_bindgen_test]
+fn restores_saved_games() {
+    let mut game = Game::new(2, 11).unwrap();
+    game.choose_category("Chance").unwrap();
+    let mut restored = Game::from_json(&game.to_json()).unwrap();
+    assert_eq!(restored.to_json(), game.to_json());
+
+    game.reroll().unwrap();
+    restored.reroll().unwrap();
+    assert_eq!(restored.dice(), game.dice());
+    assert!(Game::from_json("{}").is_err());
+}
+
+#[wasm_bindgen_test]
+fn gives_hints() {
+    let game = Game::new(2, 5).unwrap();
+    let solver = Solver::new();
+    let hint = solver.hint(&game, "expected").unwrap();
+    assert!(hint.contains("hold") || hint.contains("category"));
+    assert!(solver.hint(&game, "nonsense").is_err());
+
+    let chances = solver.win_chances(&game);
+    assert_eq!(chances.len(), 2);
+    assert!((chances[0] - 0.5).abs() < 1e-9);
+}
@@ -0,0 +1,202 @@
+// Browser front-end of yachtee. The rules run in the WebAssembly build of
+// the engine; this file only draws the game and forwards clicks to it.
+
+import init, {
+  Game,
+  Solver,
+  category_label,
+  dice_face,
+  lower_section,
+  upper_section,
+} from "./pkg/yachtee.js";
+
+const NUM_DICE = 5;
+
+let game;
+let solver;
+let message = "";
+
+// A seed in the URL, as in ?seed=42, replays the same dice.
+function newSeed() {
+  const seed = new URLSearchParams(location.search).get("seed");
+  if (seed !== null) {
+    return BigInt(seed);
+  }
+  return crypto.getRandomValues(new BigUint64Array(1))[0];
+}
+
+function newGame() {
+  const players = Number(document.getElementById("players").value) || 1;
+  game = new Game(players, newSeed());
+  message = "";
+  draw();
+}
+
+function playerName(player) {
+  return game.players() > 1 ? `Player ${player + 1}` : "Score";
+}
+
+function drawStatus() {
+  const text = [];
+  if (game.is_finished()) {
+    text.push("Game over");
+  } else {
+    if (game.players() > 1) {
+      text.push(`${playerName(game.current_player())} / ${game.players()}`);
+    }
+    text.push(`Roll ${game.roll()} / ${game.max_rolls()}`);
+  }
+  document.getElementById("status").textContent = text.join("    ");
+}
+
+function drawDice() {
+  const container = document.getElementById("dice");
+  container.replaceChildren();
+  game.dice().forEach((pips, i) => {
+    const dice = document.createElement("div");
+    const face = document.createElement("pre");
+    face.className = "dice" + (game.is_held(i) ? " held" : "");
+    face.textContent = dice_face(pips, false);
+    face.addEventListener("click", () => toggleHold(i));
+    const number = document.createElement("div");
+    number.className = "dice-number";
+    number.textContent = game.is_held(i) ? "held" : String(i + 1);
+    dice.append(face, number);
+    container.append(dice);
+  });
+}
+
+function row(table, label, values, className) {
+  const tr = table.insertRow();
+  if (className) {
+    tr.className = className;
+  }
+  tr.insertCell().textContent = label;
+  for (const value of values) {
+    const td = tr.insertCell();
+    td.className = "value";
+    td.textContent = value === undefined ? "" : String(value);
+  }
+  return tr;
+}
+
+function section(table, title) {
+  const th = document.createElement("th");
+  th.colSpan = game.players() + 1;
+  th.textContent = title;
+  table.insertRow().append(th);
+}
+
+function drawCategories(table, categories) {
+  const players = [...Array(game.players()).keys()];
+  const current = game.current_player();
+  for (const category of categories) {
+    const available = !game.is_finished() && game.category_is_available(category);
+    const values = players.map((player) => {
+      const score = game.category_score(player, category);
+      if (player === current && available) {
+        const preview = game.potential_score(category);
+        // a bonus five of a kind is added to the existing score
+        return score === undefined ? preview : `+${preview}`;
+      }
+      return score;
+    });
+    let className = "";
+    if (available) {
+      className = game.potential_score(category) === 0 ? "available zero" : "available";
+    }
+    const tr = row(table, category_label(category, false), values, className);
+    if (available) {
+      tr.addEventListener("click", () => chooseCategory(category));
+    }
+  }
+}
+
+function drawTable() {
+  const table = document.getElementById("table");
+  table.replaceChildren();
+  const players = [...Array(game.players()).keys()];
+
+  row(table, "", players.map(playerName));
+  section(table, "Upper Section");
+  drawCategories(table, upper_section());
+  row(table, "Subtotal", players.map((p) => game.basic_total(p)));
+  row(table, "Bonus if > 62", players.map((p) => game.upper_section_bonus(p)));
+  row(table, "Total", players.map((p) => game.upper_total(p)));
+
+  section(table, "Lower Section");
+  drawCategories(table, lower_section());
+  row(table, "Total", players.map((p) => game.lower_total(p)));
+  row(table, "Grand Total", players.map((p) => game.grand_total(p)));
+}
+
+function draw() {
+  drawStatus();
+  drawDice();
+  drawTable();
+  document.getElementById("roll").disabled = !game.can_reroll();
+  document.getElementById("hint").disabled = game.is_finished();
+  document.getElementById("message").textContent = message;
+}
+
+function toggleHold(dice) {
+  if (game.can_reroll()) {
+    game.toggle_hold(dice);
+    draw();
+  }
+}
+
+function roll() {
+  try {
+    game.reroll();
+    message = "";
+  } catch (e) {
+    message = e.message;
+  }
+  draw();
+}
+
+function chooseCategory(category) {
+  const player = game.current_player();
+  const points = game.choose_category(category);
+  message = `${playerName(player)}: ${category_label(category, false)} +${points}`;
+  draw();
+}
+
+function hint() {
+  // the solver takes a moment to set up, so it is made on first use
+  solver = solver || new Solver();
+  const decision = JSON.parse(solver.hint(game, game.players() > 1 ? "win" : "expected"));
+  if (decision.category !== undefined) {
+    message = `Hint: score ${category_label(decision.category, false)}`;
+  } else {
+    const rerolled = [];
+    for (let i = 0; i < NUM_DICE; i++) {
+      if (!decision.hold[i]) {
+        rerolled.push(i + 1);
+      }
+    }
+    message = `Hint: reroll dice ${rerolled.join(", ")}`;
+  }
+  draw();
+}
+
+document.addEventListener("keydown", (event) => {
+  if (event.target instanceof HTMLInputElement) {
+    return;
+  }
+  const number = Number(event.key);
+  if (number >= 1 && number <= NUM_DICE) {
+    toggleHold(number - 1);
+  } else if (event.key === "r" && game.can_reroll()) {
+    roll();
+  } else if (event.key === "h" && !game.is_finished()) {
+    hint();
+  }
+});
+document.getElementById("roll").addEventListener("click", roll);
+document.getElementById("hint").addEventListener("click", hint);
+document.getElementById("new-game").addEventListener("click", newGame);
+
+await init();
+newGame();